@@ -1,24 +1,40 @@
 use divan::black_box;
 use divan::Bencher;
 use pke_rust::el_gamal::algorithms::ElGamal;
+use pke_rust::el_gamal::exponential::{BsgsTable, ExpElGamal};
 use pke_rust::traits::public_enc::PublicEnc;
-use rug::rand::RandState;
+use pke_rust::utils::rand::OsRandGen;
+use rug::Integer;
 
 const SECURY_LEVEL: u64 = 80;
 const VALUES: &[u64] = &[1, 5, 10, 20, 30, 40];
 
 #[divan::bench()]
 fn el_gamal_keygen(bencher: Bencher) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
 
     bencher.bench_local(|| {
         let _ = ElGamal::keygen(black_box(SECURY_LEVEL), black_box(&mut rng)).unwrap();
     });
 }
 
+#[divan::bench(args=[2, 4, 8])]
+fn el_gamal_keygen_parallel(bencher: Bencher, threads: usize) {
+    let mut rng = OsRandGen::new();
+
+    bencher.bench_local(|| {
+        let _ = ElGamal::keygen_parallel(
+            black_box(SECURY_LEVEL),
+            black_box(threads),
+            black_box(&mut rng),
+        )
+        .unwrap();
+    });
+}
+
 #[divan::bench(args=VALUES)]
 fn el_gamal_encrypt(bencher: Bencher, m: u64) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let (_, pk) = ElGamal::keygen(SECURY_LEVEL, &mut rng).unwrap();
 
     let m = m.to_le_bytes();
@@ -30,14 +46,62 @@ fn el_gamal_encrypt(bencher: Bencher, m: u64) {
 
 #[divan::bench(args=VALUES)]
 fn el_gamal_decrypt(bencher: Bencher, m: u64) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let (sk, pk) = ElGamal::keygen(SECURY_LEVEL, &mut rng).unwrap();
 
     let m: [u8; 8] = m.to_le_bytes();
     let c = ElGamal::encrypt(&pk, &m, &mut rng).unwrap();
 
     bencher.bench_local(|| {
-        let _ = ElGamal::decrypt(black_box(&pk), black_box(&sk), black_box(&c)).unwrap();
+        let _ = ElGamal::decrypt(
+            black_box(&pk),
+            black_box(&sk),
+            black_box(&c),
+            black_box(&mut rng),
+        )
+        .unwrap();
+    });
+}
+
+#[divan::bench(args=VALUES)]
+fn exp_el_gamal_encrypt(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (_, pk) = ExpElGamal::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = Integer::from(m);
+
+    bencher.bench_local(|| {
+        let _ = ExpElGamal::encrypt_integer(black_box(&pk), black_box(&m), black_box(&mut rng))
+            .unwrap();
+    });
+}
+
+#[divan::bench(args=VALUES)]
+fn exp_el_gamal_decrypt_with_table(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = ExpElGamal::keygen(SECURY_LEVEL, &mut rng).unwrap();
+    let table = BsgsTable::new(&pk, ExpElGamal::DEFAULT_BOUND).unwrap();
+
+    let c = ExpElGamal::encrypt_integer(&pk, &Integer::from(m), &mut rng).unwrap();
+
+    bencher.bench_local(|| {
+        let _ = ExpElGamal::decrypt_with_table(
+            black_box(&pk),
+            black_box(&sk.key),
+            black_box(&c),
+            black_box(&table),
+        )
+        .unwrap();
+    });
+}
+
+#[divan::bench()]
+fn exp_el_gamal_bsgs_table(bencher: Bencher) {
+    let mut rng = OsRandGen::new();
+    let (_, pk) = ExpElGamal::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    bencher.bench_local(|| {
+        let _ = BsgsTable::new(black_box(&pk), black_box(ExpElGamal::DEFAULT_BOUND)).unwrap();
     });
 }
 
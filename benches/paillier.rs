@@ -2,20 +2,22 @@ use divan::black_box;
 use divan::Bencher;
 use pke_rust::paillier::algorithms::Paillier;
 use pke_rust::traits::public_enc::PublicEnc;
-use rug::rand::RandState;
+use pke_rust::utils::rand::{secure_rand_state, OsRandGen};
+use rug::integer::Order;
+use rug::{Complete, Integer};
 
 const SECURY_LEVEL: u64 = 80;
 const VALUES: &[u64] = &[1, 5, 10, 20, 30, 40];
 
 #[divan::bench]
 fn paillier_keygen() {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let _ = Paillier::keygen(black_box(SECURY_LEVEL), black_box(&mut rng)).unwrap();
 }
 
 #[divan::bench(args=VALUES)]
 fn paillier_encrypt(bencher: Bencher, m: u64) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let (_, pk) = Paillier::keygen(SECURY_LEVEL, &mut rng).unwrap();
 
     let m = m.to_le_bytes();
@@ -25,16 +27,58 @@ fn paillier_encrypt(bencher: Bencher, m: u64) {
     });
 }
 
+// Reference encryption computing g^m mod n^2 with a full exponentiation
+#[divan::bench(args=VALUES)]
+fn paillier_encrypt_generic_g(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (_, pk) = Paillier::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = Integer::from_digits(&m.to_le_bytes(), Order::MsfBe);
+    let r = pk.n.random_below_ref(&mut secure_rand_state()).complete();
+
+    bencher.bench_local(|| {
+        let g_m =
+            pk.g.secure_pow_mod_ref(black_box(&m), &pk.n_square)
+                .complete();
+        let r_n = r.secure_pow_mod_ref(&pk.n, &pk.n_square).complete();
+        let _ = (g_m * r_n).modulo(&pk.n_square);
+    });
+}
+
 #[divan::bench(args=VALUES)]
 fn paillier_decrypt(bencher: Bencher, m: u64) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = Paillier::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m: [u8; 8] = m.to_le_bytes();
+    let c = Paillier::encrypt(&pk, &m, &mut rng).unwrap();
+
+    bencher.bench_local(|| {
+        let _ = Paillier::decrypt(
+            black_box(&pk),
+            black_box(&sk),
+            black_box(&c),
+            black_box(&mut rng),
+        )
+        .unwrap();
+    });
+}
+
+// Reference decryption computing L(c^lambda mod n^2) * mu mod n without the CRT
+#[divan::bench(args=VALUES)]
+fn paillier_decrypt_no_crt(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
     let (sk, pk) = Paillier::keygen(SECURY_LEVEL, &mut rng).unwrap();
 
     let m: [u8; 8] = m.to_le_bytes();
     let c = Paillier::encrypt(&pk, &m, &mut rng).unwrap();
+    let c = Integer::from_digits(&c, Order::MsfBe);
 
     bencher.bench_local(|| {
-        let _ = Paillier::decrypt(black_box(&pk), black_box(&sk), black_box(&c)).unwrap();
+        let x = black_box(&c)
+            .secure_pow_mod_ref(&sk.lambda, &pk.n_square)
+            .complete();
+        let _ = ((x - Integer::ONE) / &pk.n * &sk.mu).modulo(&pk.n);
     });
 }
 
@@ -1,20 +1,20 @@
 use divan::black_box;
 use divan::Bencher;
+use pke_rust::utils::rand::OsRandGen;
 use pke_rust::{rsa::algorithms::RSA, traits::public_enc::PublicEnc};
-use rug::rand::RandState;
 
 const SECURY_LEVEL: u64 = 80;
 const VALUES: &[u64] = &[1, 5, 10, 20, 30, 40];
 
 #[divan::bench]
 fn rsa_keygen() {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let _ = RSA::keygen(black_box(SECURY_LEVEL), black_box(&mut rng)).unwrap();
 }
 
 #[divan::bench(args=VALUES)]
 fn rsa_encrypt(bencher: Bencher, m: u64) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let (_, pk) = RSA::keygen(SECURY_LEVEL, &mut rng).unwrap();
 
     let m = m.to_le_bytes();
@@ -26,14 +26,20 @@ fn rsa_encrypt(bencher: Bencher, m: u64) {
 
 #[divan::bench(args=VALUES)]
 fn rsa_decrypt(bencher: Bencher, m: u64) {
-    let mut rng = RandState::new();
+    let mut rng = OsRandGen::new();
     let (sk, pk) = RSA::keygen(SECURY_LEVEL, &mut rng).unwrap();
 
     let m = m.to_le_bytes();
     let c = RSA::encrypt(&pk, &m, &mut rng).unwrap();
 
     bencher.bench_local(|| {
-        let _ = RSA::decrypt(black_box(&pk), black_box(&sk), black_box(&c)).unwrap();
+        let _ = RSA::decrypt(
+            black_box(&pk),
+            black_box(&sk),
+            black_box(&c),
+            black_box(&mut rng),
+        )
+        .unwrap();
     });
 }
 
@@ -0,0 +1,51 @@
+use divan::black_box;
+use divan::Bencher;
+use pke_rust::rsaoaep::algorithms::RSAOAEP;
+use pke_rust::traits::public_enc::PublicEnc;
+use pke_rust::utils::rand::OsRandGen;
+use sha2::Sha256;
+
+const SECURY_LEVEL: u64 = 80;
+const VALUES: &[u64] = &[1, 5, 10, 20, 30, 40];
+
+#[divan::bench]
+fn rsa_oaep_keygen() {
+    let mut rng = OsRandGen::new();
+    let _ = RSAOAEP::<Sha256>::keygen(black_box(SECURY_LEVEL), black_box(&mut rng)).unwrap();
+}
+
+#[divan::bench(args=VALUES)]
+fn rsa_oaep_encrypt(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (_, pk) = RSAOAEP::<Sha256>::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = m.to_le_bytes();
+
+    bencher.bench_local(|| {
+        let _ =
+            RSAOAEP::<Sha256>::encrypt(black_box(&pk), black_box(&m), black_box(&mut rng)).unwrap();
+    });
+}
+
+#[divan::bench(args=VALUES)]
+fn rsa_oaep_decrypt(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = RSAOAEP::<Sha256>::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m: [u8; 8] = m.to_le_bytes();
+    let c = RSAOAEP::<Sha256>::encrypt(&pk, &m, &mut rng).unwrap();
+
+    bencher.bench_local(|| {
+        let _ = RSAOAEP::<Sha256>::decrypt(
+            black_box(&pk),
+            black_box(&sk),
+            black_box(&c),
+            black_box(&mut rng),
+        )
+        .unwrap();
+    });
+}
+
+fn main() {
+    divan::main();
+}
@@ -0,0 +1,44 @@
+use divan::black_box;
+use divan::Bencher;
+use pke_rust::rsapss::algorithms::RSAPSS;
+use pke_rust::traits::signature::Signature;
+use pke_rust::utils::rand::OsRandGen;
+use sha2::Sha256;
+
+const SECURY_LEVEL: u64 = 80;
+const VALUES: &[u64] = &[1, 5, 10, 20, 30, 40];
+
+#[divan::bench(args=VALUES)]
+fn rsa_pss_sign(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = RSAPSS::<Sha256>::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = m.to_le_bytes();
+
+    bencher.bench_local(|| {
+        let _ = RSAPSS::<Sha256>::sign(
+            black_box(&pk),
+            black_box(&sk),
+            black_box(&m),
+            black_box(&mut rng),
+        )
+        .unwrap();
+    });
+}
+
+#[divan::bench(args=VALUES)]
+fn rsa_pss_verify(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = RSAPSS::<Sha256>::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = m.to_le_bytes();
+    let s = RSAPSS::<Sha256>::sign(&pk, &sk, &m, &mut rng).unwrap();
+
+    bencher.bench_local(|| {
+        RSAPSS::<Sha256>::verify(black_box(&pk), black_box(&m), black_box(&s)).unwrap();
+    });
+}
+
+fn main() {
+    divan::main();
+}
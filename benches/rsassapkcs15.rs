@@ -0,0 +1,44 @@
+use divan::black_box;
+use divan::Bencher;
+use pke_rust::rsassapkcs15::algorithms::RSASSAPKCS15;
+use pke_rust::traits::signature::Signature;
+use pke_rust::utils::rand::OsRandGen;
+use sha2::Sha256;
+
+const SECURY_LEVEL: u64 = 80;
+const VALUES: &[u64] = &[1, 5, 10, 20, 30, 40];
+
+#[divan::bench(args=VALUES)]
+fn rsa_ssa_pkcs15_sign(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = RSASSAPKCS15::<Sha256>::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = m.to_le_bytes();
+
+    bencher.bench_local(|| {
+        let _ = RSASSAPKCS15::<Sha256>::sign(
+            black_box(&pk),
+            black_box(&sk),
+            black_box(&m),
+            black_box(&mut rng),
+        )
+        .unwrap();
+    });
+}
+
+#[divan::bench(args=VALUES)]
+fn rsa_ssa_pkcs15_verify(bencher: Bencher, m: u64) {
+    let mut rng = OsRandGen::new();
+    let (sk, pk) = RSASSAPKCS15::<Sha256>::keygen(SECURY_LEVEL, &mut rng).unwrap();
+
+    let m = m.to_le_bytes();
+    let s = RSASSAPKCS15::<Sha256>::sign(&pk, &sk, &m, &mut rng).unwrap();
+
+    bencher.bench_local(|| {
+        RSASSAPKCS15::<Sha256>::verify(black_box(&pk), black_box(&m), black_box(&s)).unwrap();
+    });
+}
+
+fn main() {
+    divan::main();
+}
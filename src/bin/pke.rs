@@ -0,0 +1,621 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    path::PathBuf,
+    process::ExitCode,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use rug::{Complete, Integer};
+use zeroize::Zeroizing;
+
+use pke_rust::{
+    el_gamal::{
+        algorithms::ElGamal,
+        groups::NamedGroup,
+        keys::{ElGamalPublicKey, ElGamalSecretKey},
+    },
+    error::Error,
+    paillier::{
+        algorithms::Paillier,
+        keys::{PaillierPublicKey, PaillierSecretKey},
+    },
+    rsa::{
+        algorithms::RSA,
+        keys::{RSAPublicKey, RSASecretKey},
+    },
+    rsaoaep::algorithms::RSAOAEP,
+    rsapkcs15::algorithms::RSAPKCS15,
+    traits::public_enc::PublicEnc,
+    utils::rand::OsRandGen,
+};
+
+type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "pke",
+    about = "Public key encryption schemes from the command line"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a key pair, written to <OUT>.pub and <OUT>.key
+    Keygen {
+        #[arg(long, value_enum)]
+        scheme: Scheme,
+        #[arg(long, default_value_t = 112)]
+        sec_level: u64,
+        /// Standard group for el-gamal keys (modp2048..modp8192, ffdhe2048..ffdhe8192)
+        #[arg(long)]
+        group: Option<NamedGroup>,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Encrypt a file under a public key
+    Encrypt {
+        #[arg(long, value_enum)]
+        scheme: Scheme,
+        #[arg(long)]
+        pub_key: PathBuf,
+        #[arg(long = "in")]
+        input: PathBuf,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Decrypt a file with a secret key
+    Decrypt {
+        #[arg(long, value_enum)]
+        scheme: Scheme,
+        #[arg(long)]
+        key: PathBuf,
+        #[arg(long = "in")]
+        input: PathBuf,
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Print the type, size and public components of a key file
+    InspectKey { key: PathBuf },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Scheme {
+    Rsa,
+    RsaPkcs15,
+    RsaOaep,
+    Paillier,
+    ElGamal,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let res = match cli.command {
+        Command::Keygen {
+            scheme,
+            sec_level,
+            group,
+            out,
+        } => keygen(scheme, sec_level, group, &out),
+        Command::Encrypt {
+            scheme,
+            pub_key,
+            input,
+            out,
+        } => encrypt(scheme, &pub_key, &input, &out),
+        Command::Decrypt {
+            scheme,
+            key,
+            input,
+            out,
+        } => decrypt(scheme, &key, &input, &out),
+        Command::InspectKey { key } => inspect_key(&key),
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("pke: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn keygen(scheme: Scheme, sec_level: u64, group: Option<NamedGroup>, out: &Path) -> CliResult<()> {
+    let mut rng = OsRandGen::new();
+
+    // Named groups only exist for ElGamal
+    if group.is_some() && !matches!(scheme, Scheme::ElGamal) {
+        return Err(Error::InvalidParameters.into());
+    }
+
+    let (pk, sk) = match scheme {
+        Scheme::Rsa | Scheme::RsaPkcs15 | Scheme::RsaOaep => {
+            let (sk, pk) = RSA::keygen(sec_level, &mut rng)?;
+            (pk.to_public_key_pem()?, sk.to_pkcs1_pem(&pk)?)
+        }
+        Scheme::Paillier => {
+            let (sk, pk) = Paillier::keygen(sec_level, &mut rng)?;
+            (paillier_pk_to_text(&pk), paillier_sk_to_text(&pk, &sk))
+        }
+        Scheme::ElGamal => {
+            let (sk, pk) = match group {
+                Some(group) => ElGamal::keygen_with_group(group, &mut rng)?,
+                None => ElGamal::keygen(sec_level, &mut rng)?,
+            };
+            (el_gamal_pk_to_text(&pk), el_gamal_sk_to_text(&pk, &sk))
+        }
+    };
+
+    // Both files are created before anything is written, so that an existing
+    // key pair, or half of one, is left intact
+    let sk_path = with_suffix(out, ".key");
+    let pk_path = with_suffix(out, ".pub");
+
+    let mut sk_file = create_key_file(&sk_path, true)?;
+    let mut pk_file = match create_key_file(&pk_path, false) {
+        Ok(file) => file,
+        Err(e) => {
+            drop(sk_file);
+            fs::remove_file(&sk_path)?;
+            return Err(e.into());
+        }
+    };
+
+    sk_file.write_all(sk.as_bytes())?;
+    pk_file.write_all(pk.as_bytes())?;
+
+    Ok(())
+}
+
+// out with suffix appended, keeping any extension given by the user
+fn with_suffix(out: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(out);
+    path.push(suffix);
+
+    PathBuf::from(path)
+}
+
+// Creates a new file, readable by its owner only if private. An existing
+// file is never overwritten.
+fn create_key_file(path: &Path, private: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    if private {
+        options.mode(0o600);
+    }
+
+    #[cfg(not(unix))]
+    let _ = private;
+
+    options.open(path)
+}
+
+fn encrypt(scheme: Scheme, pub_key: &Path, input: &Path, out: &Path) -> CliResult<()> {
+    let mut rng = OsRandGen::new();
+    let key = fs::read_to_string(pub_key)?;
+    let m = fs::read(input)?;
+
+    let c = match scheme {
+        Scheme::Rsa => RSA::encrypt(&rsa_pk_from_text(&key)?, &m, &mut rng)?,
+        Scheme::RsaPkcs15 => RSAPKCS15::encrypt(&rsa_pk_from_text(&key)?, &m, &mut rng)?,
+        Scheme::RsaOaep => {
+            RSAOAEP::<sha2::Sha256>::encrypt(&rsa_pk_from_text(&key)?, &m, &mut rng)?
+        }
+        Scheme::Paillier => Paillier::encrypt(&paillier_pk_from_text(&key)?, &m, &mut rng)?,
+        Scheme::ElGamal => ElGamal::encrypt(&el_gamal_pk_from_text(&key)?, &m, &mut rng)?,
+    };
+
+    fs::write(out, c)?;
+
+    Ok(())
+}
+
+fn decrypt(scheme: Scheme, key: &Path, input: &Path, out: &Path) -> CliResult<()> {
+    let mut rng = OsRandGen::new();
+    let key = fs::read_to_string(key)?;
+    let c = fs::read(input)?;
+
+    let m = match scheme {
+        Scheme::Rsa => {
+            let (sk, pk) = rsa_sk_from_text(&key)?;
+            RSA::decrypt(&pk, &sk, &c, &mut rng)?
+        }
+        Scheme::RsaPkcs15 => {
+            let (sk, pk) = rsa_sk_from_text(&key)?;
+            RSAPKCS15::decrypt(&pk, &sk, &c, &mut rng)?
+        }
+        Scheme::RsaOaep => {
+            let (sk, pk) = rsa_sk_from_text(&key)?;
+            RSAOAEP::<sha2::Sha256>::decrypt(&pk, &sk, &c, &mut rng)?
+        }
+        Scheme::Paillier => {
+            let (sk, pk) = paillier_sk_from_text(&key)?;
+            Paillier::decrypt(&pk, &sk, &c, &mut rng)?
+        }
+        Scheme::ElGamal => {
+            let (sk, pk) = el_gamal_sk_from_text(&key)?;
+            ElGamal::decrypt(&pk, &sk, &c, &mut rng)?
+        }
+    };
+
+    fs::write(out, m)?;
+
+    Ok(())
+}
+
+// Secret components are never printed
+fn inspect_key(key: &Path) -> CliResult<()> {
+    let text = fs::read_to_string(key)?;
+
+    match pem_label(&text).ok_or(Error::Deserialization)? {
+        "PUBLIC KEY" | "RSA PUBLIC KEY" => {
+            let pk = rsa_pk_from_text(&text)?;
+            println!("RSA public key, {} bits", pk.n.significant_bits());
+            print_components(&[("n", &pk.n), ("e", &pk.e)]);
+        }
+        "PRIVATE KEY" | "RSA PRIVATE KEY" => {
+            let (_, pk) = rsa_sk_from_text(&text)?;
+            println!("RSA secret key, {} bits", pk.n.significant_bits());
+            print_components(&[("n", &pk.n), ("e", &pk.e)]);
+        }
+        PAILLIER_PUBLIC_KEY => {
+            let pk = paillier_pk_from_text(&text)?;
+            println!("Paillier public key, {} bits", pk.n.significant_bits());
+            print_components(&[("n", &pk.n), ("g", &pk.g)]);
+        }
+        PAILLIER_SECRET_KEY => {
+            let (_, pk) = paillier_sk_from_text(&text)?;
+            println!("Paillier secret key, {} bits", pk.n.significant_bits());
+            print_components(&[("n", &pk.n), ("g", &pk.g)]);
+        }
+        EL_GAMAL_PUBLIC_KEY => {
+            let pk = el_gamal_pk_from_text(&text)?;
+            println!("ElGamal public key, {} bits", pk.p.significant_bits());
+            print_components(&[("p", &pk.p), ("g", &pk.g), ("h", &pk.h)]);
+        }
+        EL_GAMAL_SECRET_KEY => {
+            let (_, pk) = el_gamal_sk_from_text(&text)?;
+            println!("ElGamal secret key, {} bits", pk.p.significant_bits());
+            print_components(&[("p", &pk.p), ("g", &pk.g), ("h", &pk.h)]);
+        }
+        _ => return Err(Error::Deserialization.into()),
+    }
+
+    Ok(())
+}
+
+fn print_components(fields: &[(&str, &Integer)]) {
+    for (name, value) in fields {
+        println!("{}: {}", name, value.to_string_radix(16));
+    }
+}
+
+// RSA keys are stored as PEM: SPKI or PKCS#1 for public keys,
+// PKCS#1 or PKCS#8 for secret keys.
+// Key files are not trusted: public keys get the full validation and
+// secret keys must match the public key stored with them
+fn rsa_pk_from_text(text: &str) -> Result<RSAPublicKey, Error> {
+    let pk = match pem_label(text) {
+        Some("PUBLIC KEY") => RSAPublicKey::from_public_key_pem(text)?,
+        Some("RSA PUBLIC KEY") => RSAPublicKey::from_pkcs1_pem(text)?,
+        _ => return Err(Error::Deserialization),
+    };
+    pk.validate()?;
+
+    Ok(pk)
+}
+
+// The decoders validate the key pair
+fn rsa_sk_from_text(text: &str) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+    match pem_label(text) {
+        Some("PRIVATE KEY") => RSASecretKey::from_pkcs8_pem(text),
+        Some("RSA PRIVATE KEY") => RSASecretKey::from_pkcs1_pem(text),
+        _ => Err(Error::Deserialization),
+    }
+}
+
+// Paillier and ElGamal keys have no standard encoding. They are stored in a
+// PEM-like armor holding one "name: hex" line per component.
+const PAILLIER_PUBLIC_KEY: &str = "PAILLIER PUBLIC KEY";
+const PAILLIER_SECRET_KEY: &str = "PAILLIER SECRET KEY";
+const EL_GAMAL_PUBLIC_KEY: &str = "ELGAMAL PUBLIC KEY";
+const EL_GAMAL_SECRET_KEY: &str = "ELGAMAL SECRET KEY";
+
+fn paillier_pk_to_text(pk: &PaillierPublicKey) -> String {
+    write_armor(PAILLIER_PUBLIC_KEY, &[("n", &pk.n), ("g", &pk.g)]).to_string()
+}
+
+fn paillier_sk_to_text(pk: &PaillierPublicKey, sk: &PaillierSecretKey) -> Zeroizing<String> {
+    write_armor(
+        PAILLIER_SECRET_KEY,
+        &[
+            ("n", &pk.n),
+            ("g", &pk.g),
+            ("p", &sk.p),
+            ("q", &sk.q),
+            ("h_p", &sk.h_p),
+            ("h_q", &sk.h_q),
+            ("q_inv", &sk.q_inv),
+            ("lambda", &sk.lambda),
+            ("mu", &sk.mu),
+        ],
+    )
+}
+
+fn paillier_pk_from_text(text: &str) -> Result<PaillierPublicKey, Error> {
+    let mut fields = read_armor(text, PAILLIER_PUBLIC_KEY)?;
+    let pk = paillier_pk_from_fields(&mut fields)?;
+    pk.validate()?;
+
+    Ok(pk)
+}
+
+fn paillier_pk_from_fields(
+    fields: &mut HashMap<String, Integer>,
+) -> Result<PaillierPublicKey, Error> {
+    let n = take(fields, "n")?;
+    let g = take(fields, "g")?;
+    let n_square = n.square_ref().complete();
+
+    Ok(PaillierPublicKey::new(n, n_square, g))
+}
+
+fn paillier_sk_from_text(text: &str) -> Result<(PaillierSecretKey, PaillierPublicKey), Error> {
+    let mut fields = read_armor(text, PAILLIER_SECRET_KEY)?;
+
+    let pk = paillier_pk_from_fields(&mut fields)?;
+    let sk = PaillierSecretKey::new(
+        take(&mut fields, "p")?,
+        take(&mut fields, "q")?,
+        take(&mut fields, "h_p")?,
+        take(&mut fields, "h_q")?,
+        take(&mut fields, "q_inv")?,
+        take(&mut fields, "lambda")?,
+        take(&mut fields, "mu")?,
+    );
+    pk.validate()?;
+    sk.validate_pair(&pk)?;
+
+    Ok((sk, pk))
+}
+
+fn el_gamal_pk_to_text(pk: &ElGamalPublicKey) -> String {
+    write_armor(
+        EL_GAMAL_PUBLIC_KEY,
+        &[("p", &pk.p), ("q", &pk.q), ("g", &pk.g), ("h", &pk.h)],
+    )
+    .to_string()
+}
+
+fn el_gamal_sk_to_text(pk: &ElGamalPublicKey, sk: &ElGamalSecretKey) -> Zeroizing<String> {
+    write_armor(
+        EL_GAMAL_SECRET_KEY,
+        &[
+            ("p", &pk.p),
+            ("q", &pk.q),
+            ("g", &pk.g),
+            ("h", &pk.h),
+            ("x", &sk.x),
+        ],
+    )
+}
+
+fn el_gamal_pk_from_text(text: &str) -> Result<ElGamalPublicKey, Error> {
+    let mut fields = read_armor(text, EL_GAMAL_PUBLIC_KEY)?;
+    let pk = el_gamal_pk_from_fields(&mut fields)?;
+    pk.validate()?;
+
+    Ok(pk)
+}
+
+fn el_gamal_pk_from_fields(
+    fields: &mut HashMap<String, Integer>,
+) -> Result<ElGamalPublicKey, Error> {
+    Ok(ElGamalPublicKey::new(
+        take(fields, "p")?,
+        take(fields, "q")?,
+        take(fields, "g")?,
+        take(fields, "h")?,
+    ))
+}
+
+fn el_gamal_sk_from_text(text: &str) -> Result<(ElGamalSecretKey, ElGamalPublicKey), Error> {
+    let mut fields = read_armor(text, EL_GAMAL_SECRET_KEY)?;
+
+    let pk = el_gamal_pk_from_fields(&mut fields)?;
+    let sk = ElGamalSecretKey::new(take(&mut fields, "x")?);
+    pk.validate()?;
+    sk.validate_pair(&pk)?;
+
+    Ok((sk, pk))
+}
+
+// Returns the label of the first "-----BEGIN <label>-----" line
+fn pem_label(text: &str) -> Option<&str> {
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("-----BEGIN "))
+        .and_then(|rest| rest.strip_suffix("-----"))
+}
+
+// The fields may be secret: the hex strings are wiped and the output is
+// allocated once, so that no copy is left behind by a reallocation
+fn write_armor(label: &str, fields: &[(&str, &Integer)]) -> Zeroizing<String> {
+    let begin = format!("-----BEGIN {}-----\n", label);
+    let end = format!("-----END {}-----\n", label);
+
+    let values: Vec<Zeroizing<String>> = fields
+        .iter()
+        .map(|(_, value)| Zeroizing::new(value.to_string_radix(16)))
+        .collect();
+
+    let len = fields
+        .iter()
+        .zip(&values)
+        .map(|((name, _), value)| name.len() + value.len() + 3)
+        .sum::<usize>();
+
+    let mut out = Zeroizing::new(String::with_capacity(begin.len() + len + end.len()));
+    out.push_str(&begin);
+
+    for ((name, _), value) in fields.iter().zip(&values) {
+        out.push_str(name);
+        out.push_str(": ");
+        out.push_str(value);
+        out.push('\n');
+    }
+
+    out.push_str(&end);
+
+    out
+}
+
+fn read_armor(text: &str, label: &str) -> Result<HashMap<String, Integer>, Error> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+
+    let mut lines = text.lines().map(str::trim).skip_while(|l| l.is_empty());
+
+    if lines.next() != Some(begin.as_str()) {
+        return Err(Error::Deserialization);
+    }
+
+    let mut fields = HashMap::new();
+
+    for line in lines.by_ref() {
+        if line == end {
+            return Ok(fields);
+        }
+
+        let (name, value) = line.split_once(':').ok_or(Error::Deserialization)?;
+        let value =
+            Integer::from_str_radix(value.trim(), 16).map_err(|_| Error::Deserialization)?;
+
+        if fields.insert(name.trim().to_string(), value).is_some() {
+            return Err(Error::Deserialization);
+        }
+    }
+
+    Err(Error::Deserialization)
+}
+
+fn take(fields: &mut HashMap<String, Integer>, name: &str) -> Result<Integer, Error> {
+    fields.remove(name).ok_or(Error::Deserialization)
+}
+
+#[cfg(test)]
+mod test {
+    use pke_rust::{
+        el_gamal::algorithms::ElGamal, error::Error, paillier::algorithms::Paillier,
+        traits::public_enc::PublicEnc, utils::rand::OsRandGen,
+    };
+
+    use super::*;
+
+    #[test]
+    fn pke_paillier_key_files_round_trip() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = Paillier::keygen(80, &mut rng).unwrap();
+
+        let pk_text = paillier_pk_to_text(&pk);
+        let sk_text = paillier_sk_to_text(&pk, &sk);
+        assert_eq!(pem_label(&pk_text), Some(PAILLIER_PUBLIC_KEY));
+
+        let parsed_pk = paillier_pk_from_text(&pk_text).unwrap();
+        let (parsed_sk, _) = paillier_sk_from_text(&sk_text).unwrap();
+
+        let c = Paillier::encrypt(&parsed_pk, b"t1", &mut rng).unwrap();
+        assert_eq!(
+            Paillier::decrypt(&pk, &parsed_sk, &c, &mut rng).unwrap(),
+            b"t1"
+        );
+
+        // A public key file is not a secret key file
+        assert_eq!(
+            paillier_sk_from_text(&pk_text).unwrap_err(),
+            Error::Deserialization
+        );
+    }
+
+    #[test]
+    fn pke_el_gamal_key_files_round_trip() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+
+        let pk_text = el_gamal_pk_to_text(&pk);
+        let sk_text = el_gamal_sk_to_text(&pk, &sk);
+
+        let parsed_pk = el_gamal_pk_from_text(&pk_text).unwrap();
+        let (parsed_sk, _) = el_gamal_sk_from_text(&sk_text).unwrap();
+
+        let c = ElGamal::encrypt(&parsed_pk, b"test1", &mut rng).unwrap();
+        assert_eq!(
+            ElGamal::decrypt(&pk, &parsed_sk, &c, &mut rng).unwrap(),
+            b"test1"
+        );
+
+        let truncated = sk_text.replace("-----END ELGAMAL SECRET KEY-----\n", "");
+        assert_eq!(
+            el_gamal_sk_from_text(&truncated).unwrap_err(),
+            Error::Deserialization
+        );
+    }
+
+    #[test]
+    fn pke_key_files_are_private_and_not_overwritten() {
+        let dir = std::env::temp_dir().join(format!("pke-keygen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("alice.v2");
+        let sk_path = dir.join("alice.v2.key");
+        let pk_path = dir.join("alice.v2.pub");
+
+        keygen(Scheme::Paillier, 80, None, &out).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&sk_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // The existing key pair is kept
+        let sk_text = fs::read_to_string(&sk_path).unwrap();
+        let pk_text = fs::read_to_string(&pk_path).unwrap();
+
+        let err = keygen(Scheme::Paillier, 80, None, &out).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(fs::read_to_string(&sk_path).unwrap(), sk_text);
+        assert_eq!(fs::read_to_string(&pk_path).unwrap(), pk_text);
+
+        // So is a public key left alone, and no secret key is created for it
+        fs::remove_file(&sk_path).unwrap();
+
+        let err = keygen(Scheme::Paillier, 80, None, &out).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<io::Error>().unwrap().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        assert_eq!(fs::read_to_string(&pk_path).unwrap(), pk_text);
+        assert!(!sk_path.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
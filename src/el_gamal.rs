@@ -1,2 +1,5 @@
 pub mod algorithms;
+pub mod ciphertext;
+pub mod exponential;
+pub mod groups;
 pub mod keys;
\ No newline at end of file
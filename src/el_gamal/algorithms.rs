@@ -1,90 +1,108 @@
-use rug::{
-    integer::{IntegerExt64, IsPrime, Order},
-    rand::RandState,
-    Assign, Complete, Integer,
+use rug::{integer::Order, rand::RandState, Assign, Complete, Integer};
+
+use crate::{
+    error::Error,
+    traits::public_enc::PublicEnc,
+    utils::{
+        primes,
+        rand::{self, SecureRng},
+    },
 };
 
-use crate::traits::public_enc::PublicEnc;
-
-use super::keys::{ElGamalPublicKey, ElGamalSecretKey};
+use super::{
+    ciphertext::ElGamalCiphertext,
+    groups::NamedGroup,
+    keys::{ElGamalPublicKey, ElGamalSecretKey},
+};
 
 pub struct ElGamal;
 
 impl ElGamal {
-    const SMALL_PRIMES: &[u32] = &[3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
-
-    fn get_mod_bits(sec_level: u64) -> Result<u64, &'static str> {
+    fn get_mod_bits(sec_level: u64) -> Result<u64, Error> {
         let mod_bits = match sec_level {
             80 => 1024,
             112 => 2048,
             128 => 3072,
             192 => 7680,
             256 => 15360,
-            _ => return Err("Invalid security level."),
+            _ => return Err(Error::InvalidSecurityLevel),
         };
 
         Ok(mod_bits)
     }
-}
 
-impl PublicEnc for ElGamal {
-    type SecretKey = ElGamalSecretKey;
-    type PublicKey = ElGamalPublicKey;
+    // Maps a message into QR_p, the subgroup of order q generated by g.
+    // Since p = 2q + 1 with q odd, p = 3 mod 4 and -1 is not a quadratic residue,
+    // so exactly one of y, p - y is in QR_p for every y in [1, q].
+    pub fn encode(pk: &ElGamalPublicKey, plaintext: &[u8]) -> Result<Integer, Error> {
+        let m = Integer::from_digits(plaintext, Order::MsfBe);
 
-    fn keygen(
-        sec_level: u64,
-        rng: &mut RandState,
-    ) -> Result<(Self::SecretKey, Self::PublicKey), String> {
-        let p_bits = Self::get_mod_bits(sec_level)?;
-        let q_bits = p_bits - 1;
+        if m >= pk.q {
+            return Err(Error::MessageOutOfRange);
+        }
 
-        let mut p = Integer::new();
-        let mut q = Integer::new();
+        let y = m + Integer::ONE;
 
-        loop {
-            q.assign(Integer::random_bits_64(q_bits, rng));
-            q.set_bit((q_bits - 1) as u32, true);
-            q.set_bit(0, true);
+        if y.legendre(&pk.p) == 1 {
+            Ok(y)
+        } else {
+            Ok(&pk.p - y)
+        }
+    }
 
-            if q.mod_u(3) != 2 {
-                continue;
-            }
+    // Inverse of encode: y in [1, q] is the representative of {y, p - y}
+    pub fn decode(pk: &ElGamalPublicKey, y: &Integer) -> Result<Vec<u8>, Error> {
+        if !Self::is_in_subgroup(pk, y) {
+            return Err(Error::Decryption);
+        }
 
-            let mut is_composite = false;
-            for &sp in Self::SMALL_PRIMES {
-                let rem_q = q.mod_u(sp);
-                if rem_q == 0 {
-                    is_composite = true;
-                    break;
-                }
-
-                if (2 * rem_q + 1) % sp == 0 {
-                    is_composite = true;
-                    break;
-                }
-            }
-            if is_composite {
-                continue;
-            }
+        let y = if y <= &pk.q {
+            y.clone()
+        } else {
+            (&pk.p - y).complete()
+        };
 
-            if q.is_probably_prime(2) == IsPrime::No {
-                continue;
-            }
+        let m = y - Integer::ONE;
 
-            p.assign(&q);
-            p <<= 1;
-            p += 1;
+        Ok(m.to_digits(Order::MsfBe))
+    }
 
-            if p.is_probably_prime(12) != IsPrime::No {
-                if q.is_probably_prime(12) != IsPrime::No {
-                    break;
-                }
-            }
-        }
+    // Same as keygen, with the safe-prime search run on the given number of
+    // threads
+    pub fn keygen_parallel<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        threads: usize,
+        rng: &mut R,
+    ) -> Result<(ElGamalSecretKey, ElGamalPublicKey), Error> {
+        let p_bits = Self::get_mod_bits(sec_level)?;
+
+        rand::with_rand_state(rng, |rng| {
+            let (p, q) =
+                primes::safe_prime_parallel(p_bits, primes::mr_rounds(p_bits), threads, rng)?;
+
+            Ok(Self::keygen_in_group(p, q, Integer::from(4), rng))
+        })
+    }
 
-        // Zp generator
-        let g = Integer::from(4);
+    // Generates a key pair over a standard group: only x is random, so keys
+    // of different parties share p and g
+    pub fn keygen_with_group<R: SecureRng + ?Sized>(
+        group: NamedGroup,
+        rng: &mut R,
+    ) -> Result<(ElGamalSecretKey, ElGamalPublicKey), Error> {
+        Ok(rand::with_rand_state(rng, |rng| {
+            Self::keygen_in_group(group.p(), group.q(), group.g(), rng)
+        }))
+    }
 
+    // Generates a key pair in the subgroup of order q of Z_p*, p = 2q + 1,
+    // g being a generator of that subgroup
+    fn keygen_in_group(
+        p: Integer,
+        q: Integer,
+        g: Integer,
+        rng: &mut RandState,
+    ) -> (ElGamalSecretKey, ElGamalPublicKey) {
         // secret exponent
         let p_minus_one = (&p - Integer::ONE).complete();
         let mut x = Integer::new();
@@ -100,35 +118,80 @@ impl PublicEnc for ElGamal {
         // Compute h
         let h = g.secure_pow_mod_ref(&x, &p).complete();
 
-        let sk = Self::SecretKey::new(x);
-        let pk = Self::PublicKey::new(p, q, g, h);
+        let sk = ElGamalSecretKey::new(x);
+        let pk = ElGamalPublicKey::new(p, q, g, h);
 
-        Ok((sk, pk))
+        (sk, pk)
     }
 
-    fn encrypt(
-        pk: &Self::PublicKey,
-        plaintext: &[u8],
-        rng: &mut RandState,
-    ) -> Result<Vec<u8>, String> {
-        let m = Integer::from_digits(plaintext, Order::MsfBe);
-        let p_minus_one = (&pk.p - Integer::ONE).complete();
-
-        if m > p_minus_one {
-            return Err("The message is out of message space.".to_string());
-        }
-
-        // Generate a random k in [2,p-2]
+    // Select a random k in [1, q-1]
+    pub(crate) fn random_k(pk: &ElGamalPublicKey, rng: &mut RandState) -> Integer {
         let mut k = Integer::new();
 
         loop {
-            k.assign(p_minus_one.random_below_ref(rng).complete());
+            k.assign(pk.q.random_below_ref(rng));
 
-            if &k > Integer::ONE {
+            if !k.is_zero() {
                 break;
             }
         }
 
+        k
+    }
+
+    // Decrypts a ciphertext in the bincode format of earlier versions, which
+    // encrypted the message itself rather than its encoding in QR_p: m is
+    // c2 * c1^-x mod p as is, without subgroup checks or decoding.
+    pub fn decrypt_legacy(
+        pk: &ElGamalPublicKey,
+        sk: &ElGamalSecretKey,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let ElGamalCiphertext { c1, c2 } = ElGamalCiphertext::from_legacy_bytes(pk, ciphertext)?;
+
+        let p_minus_one = (&pk.p - Integer::ONE).complete();
+        let p_minus_one_minus_x = p_minus_one - &sk.x;
+
+        let m = (c1
+            .secure_pow_mod_ref(&p_minus_one_minus_x, &pk.p)
+            .complete()
+            * c2)
+            .modulo(&pk.p);
+
+        Ok(m.to_digits(Order::MsfBe))
+    }
+
+    // Checks that 0 < y < p and y is a quadratic residue modulo p
+    pub(crate) fn is_in_subgroup(pk: &ElGamalPublicKey, y: &Integer) -> bool {
+        !y.is_negative() && !y.is_zero() && y < &pk.p && y.legendre(&pk.p) == 1
+    }
+}
+
+impl PublicEnc for ElGamal {
+    type SecretKey = ElGamalSecretKey;
+    type PublicKey = ElGamalPublicKey;
+
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(Self::SecretKey, Self::PublicKey), Error> {
+        let p_bits = Self::get_mod_bits(sec_level)?;
+
+        rand::with_rand_state(rng, |rng| {
+            let (p, q) = primes::safe_prime(p_bits, primes::mr_rounds(p_bits), rng)?;
+
+            Ok(Self::keygen_in_group(p, q, Integer::from(4), rng))
+        })
+    }
+
+    fn encrypt<R: SecureRng + ?Sized>(
+        pk: &Self::PublicKey,
+        plaintext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let m = ElGamal::encode(pk, plaintext)?;
+        let k = rand::with_rand_state(rng, |rng| ElGamal::random_k(pk, rng));
+
         // Compute c1
         let c1 = pk.g.secure_pow_mod_ref(&k, &pk.p).complete();
 
@@ -137,25 +200,20 @@ impl PublicEnc for ElGamal {
             .modulo_ref(&pk.p)
             .complete();
 
-        let c1: Vec<u8> = c1.to_digits(Order::MsfBe);
-        let c2: Vec<u8> = c2.to_digits(Order::MsfBe);
-        let c: Vec<u8> = bincode::serialize(&(c1, c2)).unwrap();
-
-        Ok(c)
+        ElGamalCiphertext::new(c1, c2).to_bytes(pk)
     }
 
-    fn decrypt(
+    fn decrypt<R: SecureRng + ?Sized>(
         pk: &Self::PublicKey,
         sk: &Self::SecretKey,
         ciphertext: &[u8],
-    ) -> Result<Vec<u8>, String> {
-        let (c1, c2): (Vec<u8>, Vec<u8>) = match bincode::deserialize(ciphertext) {
-            Ok(c) => c,
-            Err(_) => return Err("Deserialization error.".to_string()),
-        };
+        _rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let ElGamalCiphertext { c1, c2 } = ElGamalCiphertext::from_bytes(pk, ciphertext)?;
 
-        let c1 = Integer::from_digits(&c1, Order::MsfBe);
-        let c2 = Integer::from_digits(&c2, Order::MsfBe);
+        if !ElGamal::is_in_subgroup(pk, &c1) || !ElGamal::is_in_subgroup(pk, &c2) {
+            return Err(Error::CiphertextOutOfRange);
+        }
 
         let p_minus_one = (&pk.p - Integer::ONE).complete();
         let p_minus_one_minus_x = p_minus_one - &sk.x;
@@ -166,36 +224,37 @@ impl PublicEnc for ElGamal {
             * c2)
             .modulo(&pk.p);
 
-        Ok(m.to_digits(Order::MsfBe))
+        ElGamal::decode(pk, &m)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use rug::{integer::Order, rand::RandState};
+    use rug::{integer::Order, Complete, Integer};
 
     use crate::{
-        el_gamal::algorithms::ElGamal, traits::public_enc::PublicEnc,
-        utils::rand::rug_randseed_os_rng,
+        el_gamal::{algorithms::ElGamal, ciphertext::ElGamalCiphertext, groups::NamedGroup},
+        error::Error,
+        traits::public_enc::PublicEnc,
+        utils::rand::{DeterministicRandGen, OsRandGen},
     };
 
     #[test]
     fn el_gamal_encrypt_failes_for_message_out_of_range() {
-        let mut rng = RandState::new();
-
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let mut rng = OsRandGen::new();
 
         let (_, pk) = ElGamal::keygen(80, &mut rng).unwrap();
 
         let m: Vec<u8> = pk.p.to_digits(Order::MsfBe);
-        assert!(ElGamal::encrypt(&pk, &m, &mut rng).is_err());
+        assert_eq!(
+            ElGamal::encrypt(&pk, &m, &mut rng),
+            Err(Error::MessageOutOfRange)
+        );
     }
 
     #[test]
     fn el_gamal_encrypt_works_as_expected() {
-        let mut rng = RandState::new();
-
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let mut rng = OsRandGen::new();
 
         let (sk, pk) = ElGamal::keygen(80, &mut rng).unwrap();
         let input = ["test1", "test2", "test3"];
@@ -204,8 +263,119 @@ mod test {
             let m = s.as_bytes();
             let c = ElGamal::encrypt(&pk, m, &mut rng).unwrap();
 
-            let decrypted_message = ElGamal::decrypt(&pk, &sk, &c).unwrap();
+            let decrypted_message = ElGamal::decrypt(&pk, &sk, &c, &mut rng).unwrap();
             assert_eq!(m, &decrypted_message);
         }
     }
+
+    #[test]
+    fn el_gamal_parallel_keygen_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen_parallel(80, 4, &mut rng).unwrap();
+
+        assert_eq!(pk.p.significant_bits(), 1024);
+        assert_eq!(pk.p, Integer::from(&pk.q << 1u32) + 1u32);
+
+        let m = b"test";
+        let c = ElGamal::encrypt(&pk, m, &mut rng).unwrap();
+        assert_eq!(ElGamal::decrypt(&pk, &sk, &c, &mut rng).unwrap(), m);
+
+        assert_eq!(
+            ElGamal::keygen_parallel(80, 0, &mut rng).unwrap_err(),
+            Error::InvalidParameters
+        );
+    }
+
+    #[test]
+    fn el_gamal_parallel_keygen_replays_the_rng() {
+        let mut a = DeterministicRandGen::from_seed([9; 32]);
+        let mut b = DeterministicRandGen::from_seed([9; 32]);
+
+        let (sk_a, pk_a) = ElGamal::keygen_parallel(80, 4, &mut a).unwrap();
+        let (sk_b, pk_b) = ElGamal::keygen_parallel(80, 4, &mut b).unwrap();
+
+        assert_eq!(pk_a.p, pk_b.p);
+        assert_eq!(pk_a.h, pk_b.h);
+        assert_eq!(sk_a.x, sk_b.x);
+    }
+
+    #[test]
+    fn el_gamal_keygen_with_group_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen_with_group(NamedGroup::Ffdhe2048, &mut rng).unwrap();
+        let (_, other_pk) = ElGamal::keygen_with_group(NamedGroup::Ffdhe2048, &mut rng).unwrap();
+
+        assert_eq!(pk.p, NamedGroup::Ffdhe2048.p());
+        assert_eq!(pk.g, 2);
+        assert_eq!(pk.p, other_pk.p);
+        assert_ne!(pk.h, other_pk.h);
+
+        for s in ["", "test1", "test2"] {
+            let m = s.as_bytes();
+            let c = ElGamal::encrypt(&pk, m, &mut rng).unwrap();
+
+            assert_eq!(ElGamal::decrypt(&pk, &sk, &c, &mut rng).unwrap(), m);
+        }
+    }
+
+    #[test]
+    fn el_gamal_encode_maps_messages_into_the_subgroup() {
+        let mut rng = OsRandGen::new();
+
+        let (_, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+
+        let q_minus_one = (&pk.q - Integer::ONE).complete();
+        let input = [
+            Vec::new(),
+            vec![1u8],
+            vec![2u8],
+            b"test1".to_vec(),
+            q_minus_one.to_digits(Order::MsfBe),
+        ];
+
+        for m in input {
+            let y = ElGamal::encode(&pk, &m).unwrap();
+
+            // y^q = 1 mod p iff y is in the subgroup of order q
+            assert_eq!(y.pow_mod_ref(&pk.q, &pk.p).unwrap().complete(), 1);
+            assert_eq!(ElGamal::decode(&pk, &y).unwrap(), m);
+        }
+
+        let q: Vec<u8> = pk.q.to_digits(Order::MsfBe);
+        assert_eq!(
+            ElGamal::encode(&pk, &q).unwrap_err(),
+            Error::MessageOutOfRange
+        );
+    }
+
+    #[test]
+    fn el_gamal_ciphertext_components_are_in_the_subgroup() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+
+        // m + 1 ranges over both residues and non-residues,
+        // so both branches of encode are exercised
+        for m in 0u8..8 {
+            let c = ElGamal::encrypt(&pk, &[m], &mut rng).unwrap();
+            let c = ElGamalCiphertext::from_bytes(&pk, &c).unwrap();
+
+            for c_i in [c.c1, c.c2] {
+                assert_eq!(c_i.pow_mod_ref(&pk.q, &pk.p).unwrap().complete(), 1);
+            }
+        }
+
+        // A non-residue c2 is rejected
+        let c = ElGamal::encrypt(&pk, b"test1", &mut rng).unwrap();
+        let mut c = ElGamalCiphertext::from_bytes(&pk, &c).unwrap();
+        c.c2 = &pk.p - c.c2;
+        let c = c.to_bytes(&pk).unwrap();
+
+        assert_eq!(
+            ElGamal::decrypt(&pk, &sk, &c, &mut rng),
+            Err(Error::CiphertextOutOfRange)
+        );
+    }
 }
@@ -0,0 +1,244 @@
+use rug::{integer::Order, Integer};
+
+use crate::{error::Error, utils::encoding};
+
+use super::keys::ElGamalPublicKey;
+
+// Represents an ElGamal's ciphertext (c1, c2) = (g^k, m * h^k).
+// The wire encoding is c1 || c2, each element written big-endian and
+// left-padded with zeros to the byte length of p.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElGamalCiphertext {
+    pub c1: Integer,
+    pub c2: Integer,
+}
+
+impl ElGamalCiphertext {
+    pub fn new(c1: Integer, c2: Integer) -> Self {
+        Self { c1, c2 }
+    }
+
+    pub fn from_bytes(pk: &ElGamalPublicKey, bytes: &[u8]) -> Result<Self, Error> {
+        let (c1, c2) = decode_elements(pk, bytes)?;
+
+        Ok(Self::new(c1, c2))
+    }
+
+    pub fn to_bytes(&self, pk: &ElGamalPublicKey) -> Result<Vec<u8>, Error> {
+        encode_elements(pk, &self.c1, &self.c2)
+    }
+
+    // Parses the bincode serialization of (c1, c2) produced by earlier versions
+    // of ElGamal::encrypt. Only kept to read old ciphertexts, which must be
+    // decrypted with ElGamal::decrypt_legacy. Those versions encrypted the
+    // empty message to c2 = 0, so c2 may be zero here.
+    pub fn from_legacy_bytes(pk: &ElGamalPublicKey, bytes: &[u8]) -> Result<Self, Error> {
+        let (c1, c2): (Vec<u8>, Vec<u8>) = match bincode::deserialize(bytes) {
+            Ok(c) => c,
+            Err(_) => return Err(Error::Deserialization),
+        };
+
+        let c1 = Integer::from_digits(&c1, Order::MsfBe);
+        let c2 = Integer::from_digits(&c2, Order::MsfBe);
+
+        if !is_element(pk, &c1) || !(c2.is_zero() || is_element(pk, &c2)) {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        Ok(Self::new(c1, c2))
+    }
+}
+
+// Byte length of an element of Z*_p
+pub(crate) fn element_len(pk: &ElGamalPublicKey) -> usize {
+    encoding::byte_len(&pk.p)
+}
+
+pub(crate) fn encode_elements(
+    pk: &ElGamalPublicKey,
+    c1: &Integer,
+    c2: &Integer,
+) -> Result<Vec<u8>, Error> {
+    let k = element_len(pk);
+
+    let mut out = encoding::i2osp(c1, k)?;
+    out.extend(encoding::i2osp(c2, k)?);
+
+    Ok(out)
+}
+
+pub(crate) fn decode_elements(
+    pk: &ElGamalPublicKey,
+    bytes: &[u8],
+) -> Result<(Integer, Integer), Error> {
+    let k = element_len(pk);
+
+    if bytes.len() != 2 * k {
+        return Err(Error::Deserialization);
+    }
+
+    let c1 = encoding::os2ip(&bytes[..k]);
+    let c2 = encoding::os2ip(&bytes[k..]);
+
+    if !is_element(pk, &c1) || !is_element(pk, &c2) {
+        return Err(Error::CiphertextOutOfRange);
+    }
+
+    Ok((c1, c2))
+}
+
+// Checks that c is in [1, p-1]
+fn is_element(pk: &ElGamalPublicKey, c: &Integer) -> bool {
+    !c.is_zero() && !c.is_negative() && c < &pk.p
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{integer::Order, rand::RandState, Assign, Complete, Integer};
+
+    use crate::{
+        el_gamal::{algorithms::ElGamal, ciphertext::ElGamalCiphertext, keys::ElGamalPublicKey},
+        error::Error,
+        traits::public_enc::PublicEnc,
+        utils::rand::{self, OsRandGen},
+    };
+
+    #[test]
+    fn el_gamal_ciphertext_bytes_round_trip() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+
+        let c = ElGamal::encrypt(&pk, b"test1", &mut rng).unwrap();
+        assert_eq!(c.len(), 2 * 128);
+
+        let parsed = ElGamalCiphertext::from_bytes(&pk, &c).unwrap();
+        assert_eq!(parsed.to_bytes(&pk).unwrap(), c);
+
+        // Small elements are left-padded
+        let small = ElGamalCiphertext::new(Integer::from(1), Integer::from(2));
+        let bytes = small.to_bytes(&pk).unwrap();
+        assert_eq!(bytes.len(), 2 * 128);
+        assert_eq!(bytes[127], 1);
+        assert_eq!(bytes[255], 2);
+        assert_eq!(ElGamalCiphertext::from_bytes(&pk, &bytes).unwrap(), small);
+
+        assert_eq!(ElGamal::decrypt(&pk, &sk, &c, &mut rng).unwrap(), b"test1");
+    }
+
+    #[test]
+    fn el_gamal_ciphertext_rejects_malformed_bytes() {
+        let mut rng = OsRandGen::new();
+
+        let (_, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+        let c = ElGamal::encrypt(&pk, b"test1", &mut rng).unwrap();
+
+        assert_eq!(
+            ElGamalCiphertext::from_bytes(&pk, &c[1..]).unwrap_err(),
+            Error::Deserialization
+        );
+        assert_eq!(
+            ElGamalCiphertext::from_bytes(&pk, &[]).unwrap_err(),
+            Error::Deserialization
+        );
+
+        let zero = ElGamalCiphertext::new(Integer::ZERO, Integer::from(1));
+        assert_eq!(
+            ElGamalCiphertext::from_bytes(&pk, &zero.to_bytes(&pk).unwrap()).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+
+        let p = ElGamalCiphertext::new(Integer::from(1), pk.p.clone());
+        assert_eq!(
+            ElGamalCiphertext::from_bytes(&pk, &p.to_bytes(&pk).unwrap()).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+    }
+
+    // Encrypts m as ElGamal::encrypt did before the fixed-length encoding:
+    // no encoding of the message, k in [2, p - 2], bincode tuple
+    fn legacy_encrypt(pk: &ElGamalPublicKey, m: &Integer, rng: &mut RandState) -> Vec<u8> {
+        let p_minus_one = (&pk.p - Integer::ONE).complete();
+        let mut k = Integer::new();
+
+        loop {
+            k.assign(p_minus_one.random_below_ref(rng).complete());
+
+            if &k > Integer::ONE {
+                break;
+            }
+        }
+
+        let c1 = pk.g.secure_pow_mod_ref(&k, &pk.p).complete();
+        let c2 = ((pk.h.secure_pow_mod_ref(&k, &pk.p).complete()) * m)
+            .modulo_ref(&pk.p)
+            .complete();
+
+        let c1: Vec<u8> = c1.to_digits(Order::MsfBe);
+        let c2: Vec<u8> = c2.to_digits(Order::MsfBe);
+
+        bincode::serialize(&(c1, c2)).unwrap()
+    }
+
+    #[test]
+    fn el_gamal_decrypts_legacy_ciphertexts() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+        let p_minus_one = (&pk.p - Integer::ONE).complete();
+
+        // p - 1 is not a quadratic residue, and 0 was encrypted to c2 = 0
+        let messages = [
+            Integer::from_digits(b"test1", Order::MsfBe),
+            p_minus_one.clone(),
+            Integer::ZERO,
+        ];
+
+        for m in messages {
+            let legacy = rand::with_rand_state(&mut rng, |rng| legacy_encrypt(&pk, &m, rng));
+
+            assert_eq!(
+                ElGamal::decrypt_legacy(&pk, &sk, &legacy).unwrap(),
+                m.to_digits::<u8>(Order::MsfBe)
+            );
+        }
+
+        // The current decryption rejects such ciphertexts
+        let legacy = rand::with_rand_state(&mut rng, |rng| legacy_encrypt(&pk, &p_minus_one, rng));
+        let c = ElGamalCiphertext::from_legacy_bytes(&pk, &legacy).unwrap();
+        assert_eq!(
+            ElGamal::decrypt(&pk, &sk, &c.to_bytes(&pk).unwrap(), &mut rng).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+    }
+
+    #[test]
+    fn el_gamal_ciphertext_rejects_malformed_legacy_bytes() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+
+        let p_minus_one: Vec<u8> = (&pk.p - Integer::ONE).complete().to_digits(Order::MsfBe);
+        let p: Vec<u8> = pk.p.to_digits(Order::MsfBe);
+
+        for legacy in [
+            bincode::serialize(&(p_minus_one.clone(), p.clone())).unwrap(),
+            bincode::serialize(&(p, p_minus_one.clone())).unwrap(),
+            bincode::serialize(&(Vec::<u8>::new(), p_minus_one)).unwrap(),
+        ] {
+            assert_eq!(
+                ElGamalCiphertext::from_legacy_bytes(&pk, &legacy).unwrap_err(),
+                Error::CiphertextOutOfRange
+            );
+            assert_eq!(
+                ElGamal::decrypt_legacy(&pk, &sk, &legacy).unwrap_err(),
+                Error::CiphertextOutOfRange
+            );
+        }
+
+        assert_eq!(
+            ElGamalCiphertext::from_legacy_bytes(&pk, &[1, 2, 3]).unwrap_err(),
+            Error::Deserialization
+        );
+    }
+}
@@ -0,0 +1,492 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use rug::{integer::Order, Complete, Integer};
+
+use crate::{
+    error::Error,
+    traits::public_enc::PublicEnc,
+    utils::rand::{self, SecureRng},
+};
+
+use super::{
+    algorithms::ElGamal,
+    ciphertext,
+    keys::{ElGamalPublicKey, ElGamalSecretKey},
+};
+
+// Exponential ElGamal: the message m is encrypted as (g^k, g^m * h^k).
+// The scheme is additively homomorphic, but decryption has to solve a
+// discrete logarithm, so only messages below a small bound can be recovered.
+pub struct ExpElGamal;
+
+impl ExpElGamal {
+    // Bound used by PublicEnc::decrypt
+    pub const DEFAULT_BOUND: u64 = 1 << 20;
+
+    pub fn encrypt_integer<R: SecureRng + ?Sized>(
+        pk: &ElGamalPublicKey,
+        m: &Integer,
+        rng: &mut R,
+    ) -> Result<ExpElGamalCiphertext, Error> {
+        if m.is_negative() || m >= &pk.q {
+            return Err(Error::MessageOutOfRange);
+        }
+
+        let g_m = pow_mod(&pk.g, m, &pk.p);
+        let c = ExpElGamalCiphertext::new(Integer::from(1), g_m);
+
+        Ok(c.rerandomize(pk, rng))
+    }
+
+    // Fails with InvalidKey if the table was built for another group
+    pub fn decrypt_with_table(
+        pk: &ElGamalPublicKey,
+        sk: &ElGamalSecretKey,
+        c: &ExpElGamalCiphertext,
+        table: &BsgsTable,
+    ) -> Result<u64, Error> {
+        if !table.is_for(pk) {
+            return Err(Error::InvalidKey);
+        }
+
+        if !ElGamal::is_in_subgroup(pk, &c.c1) || !ElGamal::is_in_subgroup(pk, &c.c2) {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        // g^m = c2 * c1^(p-1-x) mod p
+        let p_minus_one_minus_x = (&pk.p - Integer::ONE).complete() - &sk.x;
+        let g_m = (c
+            .c1
+            .secure_pow_mod_ref(&p_minus_one_minus_x, &pk.p)
+            .complete()
+            * &c.c2)
+            .modulo(&pk.p);
+
+        table.solve(&g_m).ok_or(Error::MessageOutOfRange)
+    }
+}
+
+impl PublicEnc for ExpElGamal {
+    type SecretKey = ExpElGamalSecretKey;
+    type PublicKey = ElGamalPublicKey;
+
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(Self::SecretKey, Self::PublicKey), Error> {
+        let (sk, pk) = ElGamal::keygen(sec_level, rng)?;
+
+        Ok((ExpElGamalSecretKey::new(sk), pk))
+    }
+
+    fn encrypt<R: SecureRng + ?Sized>(
+        pk: &Self::PublicKey,
+        plaintext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let m = Integer::from_digits(plaintext, Order::MsfBe);
+
+        ExpElGamal::encrypt_integer(pk, &m, rng)?.to_bytes(pk)
+    }
+
+    fn decrypt<R: SecureRng + ?Sized>(
+        pk: &Self::PublicKey,
+        sk: &Self::SecretKey,
+        ciphertext: &[u8],
+        _rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let c = ExpElGamalCiphertext::from_bytes(pk, ciphertext)?;
+        let table = sk.bsgs.table(pk)?;
+
+        let m = ExpElGamal::decrypt_with_table(pk, &sk.key, &c, &table)?;
+
+        Ok(Integer::from(m).to_digits(Order::MsfBe))
+    }
+}
+
+// Secret key of ExpElGamal: an ElGamal secret key, plus the baby-step
+// giant-step table for DEFAULT_BOUND that PublicEnc::decrypt builds on first
+// use and then reuses.
+pub struct ExpElGamalSecretKey {
+    pub key: ElGamalSecretKey,
+
+    bsgs: BsgsCache,
+}
+
+impl ExpElGamalSecretKey {
+    pub fn new(key: ElGamalSecretKey) -> Self {
+        Self {
+            key,
+            bsgs: BsgsCache::new(),
+        }
+    }
+}
+
+// The secret components are never printed
+impl fmt::Debug for ExpElGamalSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpElGamalSecretKey")
+            .finish_non_exhaustive()
+    }
+}
+
+// Represents an exponential ElGamal's ciphertext (c1, c2) = (g^k, g^m * h^k)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpElGamalCiphertext {
+    pub c1: Integer,
+    pub c2: Integer,
+}
+
+impl ExpElGamalCiphertext {
+    pub fn new(c1: Integer, c2: Integer) -> Self {
+        Self { c1, c2 }
+    }
+
+    // Same wire encoding as ElGamalCiphertext
+    pub fn from_bytes(pk: &ElGamalPublicKey, bytes: &[u8]) -> Result<Self, Error> {
+        let (c1, c2) = ciphertext::decode_elements(pk, bytes)?;
+
+        Ok(Self::new(c1, c2))
+    }
+
+    pub fn to_bytes(&self, pk: &ElGamalPublicKey) -> Result<Vec<u8>, Error> {
+        ciphertext::encode_elements(pk, &self.c1, &self.c2)
+    }
+
+    // E(m1) * E(m2) = E(m1 + m2)
+    pub fn add(&self, pk: &ElGamalPublicKey, other: &Self) -> Self {
+        let c1 = (&self.c1 * &other.c1).complete().modulo(&pk.p);
+        let c2 = (&self.c2 * &other.c2).complete().modulo(&pk.p);
+
+        Self::new(c1, c2)
+    }
+
+    // E(m)^s = E(s * m). Negative scalars are reduced modulo q.
+    pub fn mul_scalar<R: SecureRng + ?Sized>(
+        &self,
+        pk: &ElGamalPublicKey,
+        s: &Integer,
+        rng: &mut R,
+    ) -> Self {
+        let s = s.modulo_ref(&pk.q).complete();
+
+        // (1, 1) would give the plaintext away, so a fresh encryption of 0 is
+        // returned instead
+        if s.is_zero() {
+            return Self::new(Integer::from(1), Integer::from(1)).rerandomize(pk, rng);
+        }
+
+        let c1 = pow_mod(&self.c1, &s, &pk.p);
+        let c2 = pow_mod(&self.c2, &s, &pk.p);
+
+        Self::new(c1, c2)
+    }
+
+    // E(m) * (g^k, h^k) = E(m), with fresh randomness k
+    pub fn rerandomize<R: SecureRng + ?Sized>(&self, pk: &ElGamalPublicKey, rng: &mut R) -> Self {
+        let k = rand::with_rand_state(rng, |rng| ElGamal::random_k(pk, rng));
+
+        let c1 = (pk.g.secure_pow_mod_ref(&k, &pk.p).complete() * &self.c1).modulo(&pk.p);
+        let c2 = (pk.h.secure_pow_mod_ref(&k, &pk.p).complete() * &self.c2).modulo(&pk.p);
+
+        Self::new(c1, c2)
+    }
+}
+
+// Lookup table for the baby-step giant-step algorithm.
+// It recovers any m < bound from g^m with O(sqrt(bound)) multiplications,
+// and can be reused for every ciphertext encrypted under the same group.
+pub struct BsgsTable {
+    // Group the table was built for
+    p: Integer,
+    g: Integer,
+    bound: u64,
+    step: u64,
+    // g^j -> j, for j in [0, step)
+    baby_steps: HashMap<Integer, u64>,
+    // g^-step mod p
+    giant_step: Integer,
+}
+
+impl BsgsTable {
+    pub fn new(pk: &ElGamalPublicKey, bound: u64) -> Result<Self, Error> {
+        if bound == 0 || pk.q <= bound {
+            return Err(Error::MessageOutOfRange);
+        }
+
+        let step = bound.isqrt() + u64::from(bound.isqrt().pow(2) != bound);
+        let mut baby_steps: HashMap<Integer, u64> = HashMap::with_capacity(step as usize);
+        let mut x = Integer::from(1);
+
+        for j in 0..step {
+            baby_steps.insert(x.clone(), j);
+            x = (x * &pk.g).modulo(&pk.p);
+        }
+
+        // x = g^step
+        let giant_step = match x.invert(&pk.p) {
+            Ok(val) => val,
+            Err(_) => return Err(Error::InvalidKey),
+        };
+
+        Ok(Self {
+            p: pk.p.clone(),
+            g: pk.g.clone(),
+            bound,
+            step,
+            baby_steps,
+            giant_step,
+        })
+    }
+
+    pub fn bound(&self) -> u64 {
+        self.bound
+    }
+
+    // Checks that the table was built for the group of pk
+    pub fn is_for(&self, pk: &ElGamalPublicKey) -> bool {
+        self.p == pk.p && self.g == pk.g
+    }
+
+    // Finds m < bound s.t. g^m = y mod p
+    pub fn solve(&self, y: &Integer) -> Option<u64> {
+        let mut gamma = y.clone();
+
+        for i in 0..self.bound.div_ceil(self.step) {
+            if let Some(j) = self.baby_steps.get(&gamma) {
+                let m = i * self.step + j;
+                return (m < self.bound).then_some(m);
+            }
+
+            gamma = (gamma * &self.giant_step).modulo(&self.p);
+        }
+
+        None
+    }
+}
+
+// Table for ExpElGamal::DEFAULT_BOUND cached in ExpElGamalSecretKey. It is
+// rebuilt when asked for another group.
+struct BsgsCache(Mutex<Option<Arc<BsgsTable>>>);
+
+impl BsgsCache {
+    fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    fn table(&self, pk: &ElGamalPublicKey) -> Result<Arc<BsgsTable>, Error> {
+        let mut cache = match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        match cache.as_ref() {
+            Some(table) if table.is_for(pk) => Ok(Arc::clone(table)),
+            _ => {
+                let table = Arc::new(BsgsTable::new(pk, ExpElGamal::DEFAULT_BOUND)?);
+                *cache = Some(Arc::clone(&table));
+
+                Ok(table)
+            }
+        }
+    }
+}
+
+// secure_pow_mod requires a positive exponent
+fn pow_mod(base: &Integer, exp: &Integer, modulo: &Integer) -> Integer {
+    if exp.is_zero() {
+        return Integer::from(1);
+    }
+
+    base.secure_pow_mod_ref(exp, modulo).complete()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use rug::{integer::Order, Complete, Integer};
+
+    use crate::{
+        el_gamal::{
+            exponential::{BsgsTable, ExpElGamal, ExpElGamalCiphertext, ExpElGamalSecretKey},
+            keys::ElGamalPublicKey,
+        },
+        error::Error,
+        traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    fn setup(rng: &mut OsRandGen) -> (ExpElGamalSecretKey, ElGamalPublicKey) {
+        ExpElGamal::keygen(80, rng).unwrap()
+    }
+
+    #[test]
+    fn exp_el_gamal_encrypt_works_as_expected() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        for m in [0u64, 1, 2, 1000, ExpElGamal::DEFAULT_BOUND - 1] {
+            let m = Integer::from(m).to_digits::<u8>(Order::MsfBe);
+            let c = ExpElGamal::encrypt(&pk, &m, &mut rng).unwrap();
+
+            let decrypted_message = ExpElGamal::decrypt(&pk, &sk, &c, &mut rng).unwrap();
+            assert_eq!(m, decrypted_message);
+        }
+    }
+
+    #[test]
+    fn exp_el_gamal_decrypt_failes_for_message_above_bound() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let table = BsgsTable::new(&pk, 100).unwrap();
+
+        let c = ExpElGamal::encrypt_integer(&pk, &Integer::from(99), &mut rng).unwrap();
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Ok(99)
+        );
+
+        let c = ExpElGamal::encrypt_integer(&pk, &Integer::from(100), &mut rng).unwrap();
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Err(Error::MessageOutOfRange)
+        );
+
+        assert_eq!(
+            ExpElGamal::encrypt_integer(&pk, &pk.q, &mut rng).unwrap_err(),
+            Error::MessageOutOfRange
+        );
+    }
+
+    #[test]
+    fn exp_el_gamal_homomorphic_operations_work_as_expected() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let table = BsgsTable::new(&pk, 10_000).unwrap();
+
+        // Tally of ten yes/no votes
+        let votes = [1, 0, 1, 1, 0, 1, 1, 1, 0, 1];
+        let mut tally = ExpElGamal::encrypt_integer(&pk, &Integer::ZERO, &mut rng).unwrap();
+
+        for v in votes {
+            let c = ExpElGamal::encrypt_integer(&pk, &Integer::from(v), &mut rng).unwrap();
+            tally = tally.add(&pk, &c);
+        }
+
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &tally, &table),
+            Ok(7)
+        );
+
+        let c = tally.mul_scalar(&pk, &Integer::from(12), &mut rng);
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Ok(84)
+        );
+
+        // -1 * 7 + 10 = 3
+        let minus_seven = tally.mul_scalar(&pk, &Integer::from(-1), &mut rng);
+        let ten = ExpElGamal::encrypt_integer(&pk, &Integer::from(10), &mut rng).unwrap();
+        let c = minus_seven.add(&pk, &ten);
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Ok(3)
+        );
+
+        // A zero scalar gives a fresh encryption of 0, not (1, 1)
+        let c = tally.mul_scalar(&pk, &pk.q, &mut rng);
+        assert_ne!(
+            c,
+            ExpElGamalCiphertext::new(Integer::from(1), Integer::from(1))
+        );
+        assert_ne!(c, tally.mul_scalar(&pk, &Integer::ZERO, &mut rng));
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Ok(0)
+        );
+
+        let c = tally.rerandomize(&pk, &mut rng);
+        assert_ne!(c, tally);
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Ok(7)
+        );
+    }
+
+    #[test]
+    fn bsgs_table_solves_every_exponent_below_bound() {
+        let mut rng = OsRandGen::new();
+        let (_, pk) = setup(&mut rng);
+
+        for bound in [1, 2, 15, 16, 17] {
+            let table = BsgsTable::new(&pk, bound).unwrap();
+
+            for m in 0..bound {
+                let y =
+                    pk.g.pow_mod_ref(&Integer::from(m), &pk.p)
+                        .unwrap()
+                        .complete();
+                assert_eq!(table.solve(&y), Some(m));
+            }
+
+            let y =
+                pk.g.pow_mod_ref(&Integer::from(bound), &pk.p)
+                    .unwrap()
+                    .complete();
+            assert_eq!(table.solve(&y), None);
+        }
+
+        assert!(BsgsTable::new(&pk, 0).is_err());
+    }
+
+    #[test]
+    fn exp_el_gamal_decrypt_checks_the_table_and_the_ciphertext() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+        let (_, other_pk) = setup(&mut rng);
+
+        let c = ExpElGamal::encrypt_integer(&pk, &Integer::from(5), &mut rng).unwrap();
+
+        // A table built for another key is rejected
+        let table = BsgsTable::new(&other_pk, 100).unwrap();
+        assert!(!table.is_for(&pk));
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &c, &table),
+            Err(Error::InvalidKey)
+        );
+
+        // c2 outside of the subgroup of quadratic residues
+        let table = BsgsTable::new(&pk, 100).unwrap();
+        let bad = ExpElGamalCiphertext::new(c.c1.clone(), (&pk.p - &c.c2).complete());
+        assert_eq!(
+            ExpElGamal::decrypt_with_table(&pk, &sk.key, &bad, &table),
+            Err(Error::CiphertextOutOfRange)
+        );
+        assert_eq!(
+            ExpElGamal::decrypt(&pk, &sk, &bad.to_bytes(&pk).unwrap(), &mut rng),
+            Err(Error::CiphertextOutOfRange)
+        );
+    }
+
+    #[test]
+    fn exp_el_gamal_decrypt_reuses_the_cached_table() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+        let (_, other_pk) = setup(&mut rng);
+
+        let first = sk.bsgs.table(&pk).unwrap();
+        assert!(Arc::ptr_eq(&first, &sk.bsgs.table(&pk).unwrap()));
+
+        // Another group gets its own table
+        let other = sk.bsgs.table(&other_pk).unwrap();
+        assert!(other.is_for(&other_pk));
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+}
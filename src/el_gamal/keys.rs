@@ -1,4 +1,14 @@
-use rug::Integer;
+use std::fmt;
+
+use rug::{Complete, Integer};
+
+use crate::{
+    error::Error,
+    utils::{primes, zeroize::zeroize_integer},
+};
+
+// Smallest modulus accepted by key validation
+const MIN_MOD_BITS: u32 = 1024;
 
 // Represents the El Gamal's public key
 #[derive(Debug)]
@@ -10,7 +20,6 @@ pub struct ElGamalPublicKey {
 }
 
 // Represents the El Gamal's secret key
-#[derive(Debug)]
 pub struct ElGamalSecretKey {
     pub x: Integer,
 }
@@ -19,10 +28,140 @@ impl ElGamalPublicKey {
     pub fn new(p: Integer, q: Integer, g: Integer, h: Integer) -> Self {
         Self { p, q, g, h }
     }
+
+    // Cheap checks for a key received from a peer: p = 2q + 1 is at least
+    // MIN_MOD_BITS bits long, g and h are quadratic residues in [2, p - 2].
+    // When q is prime the quadratic residues other than 1 have order q.
+    pub fn validate_partial(&self) -> Result<(), Error> {
+        if self.p.significant_bits() < MIN_MOD_BITS || self.p != (&self.q << 1u32).complete() + 1u32
+        {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        let p_minus_one = (&self.p - Integer::ONE).complete();
+
+        for y in [&self.g, &self.h] {
+            if *y < 2 || *y >= p_minus_one || y.legendre(&self.p) != 1 {
+                return Err(Error::InvalidPublicKey);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Partial checks, plus p is a safe prime
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_partial()?;
+
+        if !primes::is_prime(&self.q) || !primes::is_prime(&self.p) {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        Ok(())
+    }
 }
 
 impl ElGamalSecretKey {
     pub fn new(x: Integer) -> Self {
         Self { x }
     }
+
+    // Checks that the secret key belongs to pk: 0 < x < p - 1 and h = g^x mod p
+    pub fn validate_pair(&self, pk: &ElGamalPublicKey) -> Result<(), Error> {
+        let p_minus_one = (&pk.p - Integer::ONE).complete();
+
+        if self.x <= 0 || self.x >= p_minus_one {
+            return Err(Error::InvalidKey);
+        }
+
+        if pk.g.secure_pow_mod_ref(&self.x, &pk.p).complete() != pk.h {
+            return Err(Error::InvalidKey);
+        }
+
+        Ok(())
+    }
+}
+
+// The secret components are never printed
+impl fmt::Debug for ElGamalSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElGamalSecretKey").finish_non_exhaustive()
+    }
+}
+
+impl Drop for ElGamalSecretKey {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.x);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::Integer;
+
+    use crate::{
+        el_gamal::{algorithms::ElGamal, groups::NamedGroup, keys::ElGamalPublicKey},
+        error::Error,
+        traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    #[test]
+    fn el_gamal_public_key_validation_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (_, pk) = ElGamal::keygen(80, &mut rng).unwrap();
+        assert!(pk.validate_partial().is_ok());
+        assert!(pk.validate().is_ok());
+
+        let (_, group_pk) = ElGamal::keygen_with_group(NamedGroup::Modp2048, &mut rng).unwrap();
+        assert!(group_pk.validate().is_ok());
+
+        let (p, q, g, h) = (&pk.p, &pk.q, &pk.g, &pk.h);
+        let p_minus_one = Integer::from(p - 1u32);
+        let non_residue = Integer::from(p - g);
+
+        let invalid = [
+            ElGamalPublicKey::new(p.clone(), Integer::from(q + 1u32), g.clone(), h.clone()),
+            ElGamalPublicKey::new(p.clone(), q.clone(), Integer::from(1), h.clone()),
+            ElGamalPublicKey::new(p.clone(), q.clone(), p_minus_one, h.clone()),
+            ElGamalPublicKey::new(p.clone(), q.clone(), non_residue.clone(), h.clone()),
+            ElGamalPublicKey::new(p.clone(), q.clone(), g.clone(), non_residue),
+            ElGamalPublicKey::new(p.clone(), q.clone(), g.clone(), p.clone()),
+            ElGamalPublicKey::new(
+                Integer::from(23),
+                Integer::from(11),
+                Integer::from(4),
+                Integer::from(9),
+            ),
+        ];
+
+        for pk in invalid {
+            assert_eq!(pk.validate_partial().unwrap_err(), Error::InvalidPublicKey);
+            assert_eq!(pk.validate().unwrap_err(), Error::InvalidPublicKey);
+        }
+
+        // p = 2q + 1 with q composite
+        let q = (Integer::from(1) << 1100u32) + 1u32;
+        let p = Integer::from(&q << 1u32) + 1u32;
+        let pk = ElGamalPublicKey::new(p, q, Integer::from(4), Integer::from(16));
+
+        assert!(pk.validate_partial().is_ok());
+        assert_eq!(pk.validate().unwrap_err(), Error::InvalidPublicKey);
+    }
+
+    #[test]
+    fn el_gamal_key_pair_validation_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = ElGamal::keygen_with_group(NamedGroup::Ffdhe2048, &mut rng).unwrap();
+        let (other_sk, other_pk) =
+            ElGamal::keygen_with_group(NamedGroup::Ffdhe2048, &mut rng).unwrap();
+
+        assert!(sk.validate_pair(&pk).is_ok());
+        assert!(other_sk.validate_pair(&other_pk).is_ok());
+
+        assert_eq!(sk.validate_pair(&other_pk).unwrap_err(), Error::InvalidKey);
+        assert_eq!(other_sk.validate_pair(&pk).unwrap_err(), Error::InvalidKey);
+    }
 }
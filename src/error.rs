@@ -0,0 +1,65 @@
+use std::fmt;
+
+// Errors returned by the schemes of this crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    // The security level is not one of the supported ones
+    InvalidSecurityLevel,
+    // The seed size is too small
+    InvalidSeedSize,
+    // A key component could not be computed during key generation
+    KeyGeneration,
+    // The key generation parameters are not supported
+    InvalidParameters,
+    // The secret key is malformed or does not match the public key
+    InvalidKey,
+    // The public key failed validation
+    InvalidPublicKey,
+    // The message is not in the message space of the scheme
+    MessageOutOfRange,
+    // The message does not fit in the padded block
+    MessageTooLong,
+    // The modulus is too short for the selected padding or hash function
+    ModulusTooShort,
+    // The ciphertext is not in the ciphertext space of the scheme
+    CiphertextOutOfRange,
+    // The ciphertext or the key could not be parsed
+    Deserialization,
+    // The key could not be encoded
+    Serialization,
+    // Decryption of a padded message failed.
+    // Every padding check reports this same variant, so that the caller cannot
+    // learn which one failed (Bleichenbacher's and Manger's attacks).
+    Decryption,
+    // The signature does not match the message
+    InvalidSignature,
+    // The result of a private-key operation failed its consistency check,
+    // it is withheld since it could leak the secret key
+    FaultDetected,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Error::InvalidSecurityLevel => "Invalid security level.",
+            Error::InvalidSeedSize => "The seed size is less than 8 bits.",
+            Error::KeyGeneration => "Error while computing the key pair.",
+            Error::InvalidParameters => "Invalid key generation parameters.",
+            Error::InvalidKey => "The secret key is malformed or does not match the public key.",
+            Error::InvalidPublicKey => "The public key is invalid.",
+            Error::MessageOutOfRange => "The message is out of range.",
+            Error::MessageTooLong => "The plaintext is too long.",
+            Error::ModulusTooShort => "The modulus is too short.",
+            Error::CiphertextOutOfRange => "The ciphertext is out of range.",
+            Error::Deserialization => "Deserialization error.",
+            Error::Serialization => "Serialization error.",
+            Error::Decryption => "Decryption error.",
+            Error::InvalidSignature => "Invalid signature.",
+            Error::FaultDetected => "A fault was detected in the private-key operation.",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,6 +1,10 @@
 pub mod el_gamal;
+pub mod error;
 pub mod paillier;
 pub mod rsa;
+pub mod rsaoaep;
 pub mod rsapkcs15;
+pub mod rsapss;
+pub mod rsassapkcs15;
 pub mod traits;
 pub mod utils;
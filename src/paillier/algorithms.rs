@@ -1,37 +1,117 @@
-use rug::{
-    integer::{IntegerExt64, IsPrime, Order},
-    Assign, Complete, Integer,
+use rug::{integer::Order, rand::RandState, Assign, Complete, Integer};
+
+use crate::{
+    error::Error,
+    traits::public_enc::PublicEnc,
+    utils::{
+        primes,
+        rand::{self, SecureRng},
+    },
 };
 
-use crate::traits::public_enc::PublicEnc;
-
-use super::keys::{PaillierPublicKey, PaillierSecretKey};
+use super::{
+    ciphertext::PaillierCiphertext,
+    keys::{PaillierPublicKey, PaillierSecretKey},
+};
 
 pub struct Paillier;
 
 impl Paillier {
-    fn get_mod_bits(sec_level: u64) -> Result<u64, &'static str> {
+    fn get_mod_bits(sec_level: u64) -> Result<u64, Error> {
         let mod_bits = match sec_level {
             80 => 1024,
             112 => 2048,
             128 => 3072,
             192 => 7680,
             256 => 15360,
-            _ => return Err("Invalid security level."),
+            _ => return Err(Error::InvalidSecurityLevel),
         };
 
         Ok(mod_bits)
     }
+
+    // Select a random 0 < r < n s.t. gcd(r,n) = 1
+    pub(crate) fn random_r(pk: &PaillierPublicKey, rng: &mut RandState) -> Integer {
+        let mut r: Integer = Integer::new();
+
+        loop {
+            r.assign(pk.n.random_below_ref(rng));
+
+            if !r.is_zero() && &r.gcd_ref(&pk.n).complete() == Integer::ONE {
+                break;
+            }
+        }
+
+        r
+    }
+
+    // Since g = n + 1, g^m mod n^2 = 1 + m*n mod n^2 (binomial theorem)
+    pub(crate) fn g_pow(pk: &PaillierPublicKey, m: &Integer) -> Integer {
+        (m * &pk.n).complete().modulo(&pk.n_square) + Integer::ONE
+    }
+
+    // Decryption modulo one of the prime factors.
+    // L_p(x) = (x - 1) / p is the analogue of L(x) = (x - 1) / n modulo p^2.
+    pub(crate) fn crt_half(c: &Integer, p: &Integer, p_square: &Integer, h_p: &Integer) -> Integer {
+        let p_minus_one = (p - Integer::ONE).complete();
+
+        let x = c
+            .modulo_ref(p_square)
+            .complete()
+            .secure_pow_mod(&p_minus_one, p_square);
+
+        ((x - Integer::ONE) / p * h_p).modulo(p)
+    }
+
+    pub fn encrypt_integer<R: SecureRng + ?Sized>(
+        pk: &PaillierPublicKey,
+        m: &Integer,
+        rng: &mut R,
+    ) -> Result<PaillierCiphertext, Error> {
+        if m.is_negative() || m >= &pk.n {
+            return Err(Error::MessageOutOfRange);
+        }
+
+        let r = rand::with_rand_state(rng, |rng| Paillier::random_r(pk, rng));
+
+        // c = g^m * r^n mod n^2
+        let c =
+            (Paillier::g_pow(pk, m) * r.secure_pow_mod(&pk.n, &pk.n_square)).modulo(&pk.n_square);
+
+        Ok(PaillierCiphertext::new(c))
+    }
+
+    pub fn decrypt_integer(
+        pk: &PaillierPublicKey,
+        sk: &PaillierSecretKey,
+        c: &PaillierCiphertext,
+    ) -> Result<Integer, Error> {
+        if c.c.is_zero() || c.c >= pk.n_square {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        // m_p = L_p(c^(p-1) mod p^2) * h_p mod p
+        let m_p = Paillier::crt_half(&c.c, &sk.p, &sk.p_square, &sk.h_p);
+
+        // m_q = L_q(c^(q-1) mod q^2) * h_q mod q
+        let m_q = Paillier::crt_half(&c.c, &sk.q, &sk.q_square, &sk.h_q);
+
+        // m = m_q + ( (m_p - m_q) * (q_inv) mod p ) * q
+        let m = ((&m_p - &m_q).complete() * &sk.q_inv).modulo(&sk.p);
+        let m = m * &sk.q + m_q;
+
+        Ok(m)
+    }
 }
 
 impl PublicEnc for Paillier {
     type SecretKey = PaillierSecretKey;
     type PublicKey = PaillierPublicKey;
 
-    fn keygen(
+    fn keygen<R: SecureRng + ?Sized>(
         sec_level: u64,
-        rng: &mut rug::rand::RandState,
-    ) -> Result<(PaillierSecretKey, PaillierPublicKey), String> {
+        rng: &mut R,
+    ) -> Result<(PaillierSecretKey, PaillierPublicKey), Error> {
         // Generate two primes p,q s.t. gcd(pq, (p-1)(q-1)) = 1
         // Note that if, |p| = |q| this property is assured, for more information
         // see Jonathan Katz, Yehuda Lindell, "Introduction to Modern Cryptography: Principles and Protocols
@@ -42,24 +122,12 @@ impl PublicEnc for Paillier {
 
         assert_eq!(p_bits, q_bits);
 
-        let mut p: Integer = Integer::new();
-        let mut q: Integer = Integer::new();
-
-        loop {
-            p.assign(Integer::random_bits_64(p_bits, rng));
-
-            if p.significant_bits_64() == p_bits && p.is_probably_prime(12) == IsPrime::Probably {
-                break;
-            }
-        }
-
-        loop {
-            q.assign(Integer::random_bits_64(q_bits, rng));
+        let (p, q) = rand::with_rand_state(rng, |rng| {
+            let p = primes::random_prime(p_bits, primes::mr_rounds(p_bits), rng)?;
+            let q = primes::random_prime_with(q_bits, primes::mr_rounds(q_bits), rng, |q| q != &p)?;
 
-            if q.significant_bits_64() == q_bits && q.is_probably_prime(12) == IsPrime::Probably {
-                break;
-            }
-        }
+            Ok::<_, Error>((p, q))
+        })?;
 
         // Compute n = pq
         let n = (&p * &q).complete();
@@ -74,64 +142,50 @@ impl PublicEnc for Paillier {
         let lambda = (&p - Integer::ONE).complete() * (&q - Integer::ONE).complete();
 
         // Compute mu = lambda^{-1}
-        let mu;
+        let mu = match lambda.invert_ref(&n) {
+            Some(x) => x.complete(),
+            None => return Err(Error::KeyGeneration),
+        };
 
-        match lambda.invert_ref(&n) {
-            Some(x) => mu = x.complete(),
-            None => return Err("Error while computing mu.".to_string()),
+        // CRT attributes, h_p = L_p(g^(p-1) mod p^2)^-1 mod p
+        // and h_q = L_q(g^(q-1) mod q^2)^-1 mod q
+        let p_square = p.square_ref().complete();
+        let q_square = q.square_ref().complete();
+
+        let h_p = Paillier::crt_half(&g, &p, &p_square, Integer::ONE).invert(&p);
+        let h_q = Paillier::crt_half(&g, &q, &q_square, Integer::ONE).invert(&q);
+        let q_inv = q.invert_ref(&p).map(|x| x.complete());
+
+        let (h_p, h_q, q_inv) = match (h_p, h_q, q_inv) {
+            (Ok(h_p), Ok(h_q), Some(q_inv)) => (h_p, h_q, q_inv),
+            _ => return Err(Error::KeyGeneration),
         };
 
         let pk = PaillierPublicKey::new(n, n_square, g);
-        let sk = PaillierSecretKey::new(lambda, mu);
+        let sk = PaillierSecretKey::new(p, q, h_p, h_q, q_inv, lambda, mu);
 
         Ok((sk, pk))
     }
 
-    fn encrypt(
+    fn encrypt<R: SecureRng + ?Sized>(
         pk: &PaillierPublicKey,
         plaintext: &[u8],
-        rng: &mut rug::rand::RandState,
-    ) -> Result<Vec<u8>, String> {
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
         let m = Integer::from_digits(plaintext, Order::MsfBe);
+        let c = Paillier::encrypt_integer(pk, &m, rng)?;
 
-        if m.is_negative() || m >= pk.n {
-            return Err("The message is not in the message space.".to_string());
-        }
-
-        // Select a random 0 < r < n s.t. gcd(r,n) = 1
-        let mut r: Integer = Integer::new();
-        let r_size = pk.n.significant_bits_64();
-
-        loop {
-            r.assign(Integer::random_bits_64(r_size, rng));
-
-            if r.significant_bits_64() == r_size && &r.gcd_ref(&pk.n).complete() == Integer::ONE {
-                break;
-            }
-        }
-
-        let mut c = pk.g.secure_pow_mod_ref(&m, &pk.n_square).complete();
-        c = (&c * &(r.secure_pow_mod(&pk.n, &pk.n_square))).complete();
-        c = c.modulo_ref(&pk.n_square).complete();
-
-        let c: Vec<u8> = c.to_digits(Order::MsfBe);
-        Ok(c)
+        c.to_bytes(pk)
     }
 
-    fn decrypt(
+    fn decrypt<R: SecureRng + ?Sized>(
         pk: &PaillierPublicKey,
         sk: &PaillierSecretKey,
         ciphertext: &[u8],
-    ) -> Result<Vec<u8>, String> {
-        let c = Integer::from_digits(ciphertext, Order::MsfBe);
-
-        if c.is_zero() || &c > &pk.n_square {
-            return Err("The ciphertext is out of range.".to_string());
-        }
-
-        let mut m = c.secure_pow_mod(&sk.lambda, &pk.n_square) * &sk.mu;
-        m = (&m - Integer::ONE).complete() / &pk.n;
-        m = m.modulo(&pk.n);
+        _rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let c = PaillierCiphertext::from_bytes(pk, ciphertext)?;
+        let m = Paillier::decrypt_integer(pk, sk, &c)?;
 
         let m: Vec<u8> = m.to_digits(Order::MsfBe);
         Ok(m)
@@ -140,30 +194,29 @@ impl PublicEnc for Paillier {
 
 #[cfg(test)]
 mod test {
-    use rug::{integer::Order, rand::RandState};
+    use rug::{integer::Order, Complete, Integer};
 
     use crate::{
-        paillier::algorithms::Paillier, traits::public_enc::PublicEnc,
-        utils::rand::rug_randseed_os_rng,
+        error::Error, paillier::algorithms::Paillier, traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
     };
 
     #[test]
     fn paillier_encrypt_failes_for_message_out_of_range() {
-        let mut rng = RandState::new();
-
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let mut rng = OsRandGen::new();
 
         let (_, pk) = Paillier::keygen(80, &mut rng).unwrap();
 
         let m: Vec<u8> = pk.n.to_digits(Order::MsfBe);
-        assert!(Paillier::encrypt(&pk, &m, &mut rng).is_err());
+        assert_eq!(
+            Paillier::encrypt(&pk, &m, &mut rng),
+            Err(Error::MessageOutOfRange)
+        );
     }
 
     #[test]
     fn paillier_encrypt_works_as_expected() {
-        let mut rng = RandState::new();
-
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let mut rng = OsRandGen::new();
 
         let (sk, pk) = Paillier::keygen(80, &mut rng).unwrap();
         let input = ["t1", "t1", "t1"];
@@ -172,8 +225,27 @@ mod test {
             let m = s.as_bytes();
             let c = Paillier::encrypt(&pk, m, &mut rng).unwrap();
 
-            let decrypted_message = Paillier::decrypt(&pk, &sk, &c).unwrap();
+            let decrypted_message = Paillier::decrypt(&pk, &sk, &c, &mut rng).unwrap();
             assert_eq!(m, &decrypted_message);
         }
     }
+
+    #[test]
+    fn paillier_crt_decryption_matches_textbook_decryption() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = Paillier::keygen(80, &mut rng).unwrap();
+        let n_minus_one = (&pk.n - Integer::ONE).complete();
+
+        for m in [Integer::ZERO, Integer::from(1), n_minus_one] {
+            let c = Paillier::encrypt_integer(&pk, &m, &mut rng).unwrap();
+
+            // m = L(c^lambda mod n^2) * mu mod n
+            let x = c.c.secure_pow_mod_ref(&sk.lambda, &pk.n_square).complete();
+            let expected = ((x - Integer::ONE) / &pk.n * &sk.mu).modulo(&pk.n);
+
+            assert_eq!(expected, m);
+            assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), m);
+        }
+    }
 }
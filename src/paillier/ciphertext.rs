@@ -0,0 +1,241 @@
+use rug::{Complete, Integer};
+
+use crate::{
+    error::Error,
+    utils::{
+        encoding,
+        rand::{self, SecureRng},
+    },
+};
+
+use super::{algorithms::Paillier, keys::PaillierPublicKey};
+
+// Represents a Paillier's ciphertext, an element of Z*_{n^2}.
+// The operations below exploit the additive homomorphism of the scheme:
+// they act on the plaintexts modulo n without decrypting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaillierCiphertext {
+    pub c: Integer,
+}
+
+impl PaillierCiphertext {
+    pub fn new(c: Integer) -> Self {
+        Self { c }
+    }
+
+    // Ciphertexts are encoded on exactly |n^2| bytes
+    pub fn from_bytes(pk: &PaillierPublicKey, bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != encoding::byte_len(&pk.n_square) {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        let c = encoding::os2ip(bytes);
+
+        if c.is_zero() || c >= pk.n_square {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        Ok(Self::new(c))
+    }
+
+    pub fn to_bytes(&self, pk: &PaillierPublicKey) -> Result<Vec<u8>, Error> {
+        encoding::i2osp(&self.c, encoding::byte_len(&pk.n_square))
+    }
+
+    // E(m1) * E(m2) = E(m1 + m2)
+    pub fn add(&self, pk: &PaillierPublicKey, other: &Self) -> Self {
+        let c = (&self.c * &other.c).complete().modulo(&pk.n_square);
+
+        Self::new(c)
+    }
+
+    // E(m1) * g^m2 = E(m1 + m2)
+    pub fn add_plaintext(&self, pk: &PaillierPublicKey, m: &Integer) -> Result<Self, Error> {
+        if m.is_negative() || m >= &pk.n {
+            return Err(Error::MessageOutOfRange);
+        }
+
+        let g_m = Paillier::g_pow(pk, m);
+        let c = (&self.c * &g_m).complete().modulo(&pk.n_square);
+
+        Ok(Self::new(c))
+    }
+
+    // E(m)^k = E(k * m). Negative scalars are reduced modulo n.
+    pub fn mul_scalar<R: SecureRng + ?Sized>(
+        &self,
+        pk: &PaillierPublicKey,
+        k: &Integer,
+        rng: &mut R,
+    ) -> Self {
+        let k = k.modulo_ref(&pk.n).complete();
+
+        // secure_pow_mod requires a positive exponent. The trivial E(0) = 1
+        // would give the plaintext away, so a fresh encryption of 0 is
+        // returned instead.
+        if k.is_zero() {
+            return Self::new(Integer::from(1)).rerandomize(pk, rng);
+        }
+
+        let c = self.c.secure_pow_mod_ref(&k, &pk.n_square).complete();
+
+        Self::new(c)
+    }
+
+    // E(m)^-1 = E(-m)
+    pub fn neg(&self, pk: &PaillierPublicKey) -> Result<Self, Error> {
+        match self.c.invert_ref(&pk.n_square) {
+            Some(c) => Ok(Self::new(c.complete())),
+            None => Err(Error::CiphertextOutOfRange),
+        }
+    }
+
+    // E(m1) * E(m2)^-1 = E(m1 - m2)
+    pub fn sub(&self, pk: &PaillierPublicKey, other: &Self) -> Result<Self, Error> {
+        Ok(self.add(pk, &other.neg(pk)?))
+    }
+
+    // E(m) * r^n = E(m), with fresh randomness r
+    pub fn rerandomize<R: SecureRng + ?Sized>(&self, pk: &PaillierPublicKey, rng: &mut R) -> Self {
+        let r = rand::with_rand_state(rng, |rng| Paillier::random_r(pk, rng));
+        let r_n = r.secure_pow_mod(&pk.n, &pk.n_square);
+        let c = (&self.c * &r_n).complete().modulo(&pk.n_square);
+
+        Self::new(c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{integer::Order, Complete, Integer};
+
+    use crate::{
+        error::Error,
+        paillier::{
+            algorithms::Paillier,
+            ciphertext::PaillierCiphertext,
+            keys::{PaillierPublicKey, PaillierSecretKey},
+        },
+        traits::public_enc::PublicEnc,
+        utils::{encoding, rand::OsRandGen},
+    };
+
+    fn setup(rng: &mut OsRandGen) -> (PaillierSecretKey, PaillierPublicKey) {
+        Paillier::keygen(80, rng).unwrap()
+    }
+
+    #[test]
+    fn paillier_ciphertext_addition_works_as_expected() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let m1 = Integer::from(1234);
+        let m2 = Integer::from(5678);
+
+        let c1 = Paillier::encrypt_integer(&pk, &m1, &mut rng).unwrap();
+        let c2 = Paillier::encrypt_integer(&pk, &m2, &mut rng).unwrap();
+
+        let c = c1.add(&pk, &c2);
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), 6912);
+
+        let c = c1.add_plaintext(&pk, &m2).unwrap();
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), 6912);
+
+        // The sum wraps around modulo n
+        let n_minus_one = (&pk.n - Integer::ONE).complete();
+        let c = c1.add_plaintext(&pk, &n_minus_one).unwrap();
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), 1233);
+
+        assert_eq!(
+            c1.add_plaintext(&pk, &pk.n).unwrap_err(),
+            Error::MessageOutOfRange
+        );
+    }
+
+    #[test]
+    fn paillier_ciphertext_scalar_multiplication_works_as_expected() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let c = Paillier::encrypt_integer(&pk, &Integer::from(21), &mut rng).unwrap();
+
+        let c2 = c.mul_scalar(&pk, &Integer::from(2), &mut rng);
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c2).unwrap(), 42);
+
+        let c0 = c.mul_scalar(&pk, &Integer::ZERO, &mut rng);
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c0).unwrap(), 0);
+
+        // A zero scalar gives a fresh encryption of 0, not the trivial one
+        assert_ne!(c0.c, 1);
+        assert_ne!(c0, c.mul_scalar(&pk, &pk.n, &mut rng));
+
+        // -1 * 21 = n - 21
+        let c_neg = c.mul_scalar(&pk, &Integer::from(-1), &mut rng);
+        let expected = (&pk.n - 21u32).complete();
+        assert_eq!(
+            Paillier::decrypt_integer(&pk, &sk, &c_neg).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn paillier_ciphertext_negation_and_subtraction_work_as_expected() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let c1 = Paillier::encrypt_integer(&pk, &Integer::from(100), &mut rng).unwrap();
+        let c2 = Paillier::encrypt_integer(&pk, &Integer::from(58), &mut rng).unwrap();
+
+        let c = c1.sub(&pk, &c2).unwrap();
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), 42);
+
+        let c = c2.sub(&pk, &c1).unwrap();
+        let expected = (&pk.n - 42u32).complete();
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), expected);
+
+        let c = c1.neg(&pk).unwrap().add(&pk, &c1);
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c).unwrap(), 0);
+    }
+
+    #[test]
+    fn paillier_ciphertext_rerandomization_works_as_expected() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let c = Paillier::encrypt_integer(&pk, &Integer::from(7), &mut rng).unwrap();
+        let c_prime = c.rerandomize(&pk, &mut rng);
+
+        assert_ne!(c, c_prime);
+        assert_eq!(Paillier::decrypt_integer(&pk, &sk, &c_prime).unwrap(), 7);
+    }
+
+    #[test]
+    fn paillier_ciphertext_bytes_round_trip() {
+        let mut rng = OsRandGen::new();
+        let (sk, pk) = setup(&mut rng);
+
+        let c = Paillier::encrypt(&pk, b"t1", &mut rng).unwrap();
+        let parsed = PaillierCiphertext::from_bytes(&pk, &c).unwrap();
+
+        assert_eq!(c.len(), encoding::byte_len(&pk.n_square));
+        assert_eq!(parsed.to_bytes(&pk).unwrap(), c);
+        assert_eq!(
+            Paillier::decrypt_integer(&pk, &sk, &parsed).unwrap(),
+            Integer::from_digits(b"t1", Order::MsfBe)
+        );
+
+        let n_square = pk.n_square.to_digits::<u8>(Order::MsfBe);
+        assert_eq!(
+            PaillierCiphertext::from_bytes(&pk, &c[1..]).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+        assert_eq!(
+            PaillierCiphertext::from_bytes(&pk, &n_square).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+        assert_eq!(
+            PaillierCiphertext::from_bytes(&pk, &[]).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+    }
+}
@@ -1,6 +1,19 @@
-use rug::Integer;
+use std::fmt;
 
-// Represents the Paillier's public key
+use rug::{Complete, Integer};
+
+use crate::{
+    error::Error,
+    utils::{primes, zeroize::zeroize_integer},
+};
+
+use super::algorithms::Paillier;
+
+// Smallest modulus accepted by key validation
+const MIN_MOD_BITS: u32 = 1024;
+
+// Represents the Paillier's public key.
+// The schemes assume g = n + 1.
 #[derive(Debug)]
 pub struct PaillierPublicKey {
     pub n: Integer,
@@ -9,8 +22,18 @@ pub struct PaillierPublicKey {
 }
 
 // Represents the Paillier's secret key
-#[derive(Debug)]
 pub struct PaillierSecretKey {
+    // Prime factors of n
+    pub p: Integer,
+    pub q: Integer,
+
+    // CRT attributes
+    pub p_square: Integer,
+    pub q_square: Integer,
+    pub h_p: Integer,
+    pub h_q: Integer,
+    pub q_inv: Integer,
+
     pub lambda: Integer,
     pub mu: Integer,
 }
@@ -19,10 +42,177 @@ impl PaillierPublicKey {
     pub fn new(n: Integer, n_square: Integer, g: Integer) -> Self {
         PaillierPublicKey { n, n_square, g }
     }
+
+    // Cheap checks for a key received from a peer: n is odd and at least
+    // MIN_MOD_BITS bits long, n_square = n^2 and g = n + 1
+    pub fn validate_partial(&self) -> Result<(), Error> {
+        if self.n.significant_bits() < MIN_MOD_BITS || self.n.is_even() {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        if self.n_square != self.n.square_ref().complete()
+            || self.g != (&self.n + Integer::ONE).complete()
+        {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        Ok(())
+    }
+
+    // Partial checks, plus n is neither a prime, a perfect power nor a
+    // multiple of a small prime
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_partial()?;
+
+        if primes::has_small_factor(&self.n)
+            || primes::is_prime(&self.n)
+            || self.n.is_perfect_power()
+        {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        Ok(())
+    }
 }
 
 impl PaillierSecretKey {
-    pub fn new(lambda: Integer, mu: Integer) -> Self {
-        PaillierSecretKey { lambda, mu }
+    pub fn new(
+        p: Integer,
+        q: Integer,
+        h_p: Integer,
+        h_q: Integer,
+        q_inv: Integer,
+        lambda: Integer,
+        mu: Integer,
+    ) -> Self {
+        let p_square = p.square_ref().complete();
+        let q_square = q.square_ref().complete();
+
+        PaillierSecretKey {
+            p,
+            q,
+            p_square,
+            q_square,
+            h_p,
+            h_q,
+            q_inv,
+            lambda,
+            mu,
+        }
+    }
+
+    // Checks that the secret key belongs to pk: n = pq, lambda = (p-1)(q-1),
+    // mu = lambda^-1 mod n and the CRT attributes are derived from p, q and g
+    pub fn validate_pair(&self, pk: &PaillierPublicKey) -> Result<(), Error> {
+        if (&self.p * &self.q).complete() != pk.n
+            || self.p_square != self.p.square_ref().complete()
+            || self.q_square != self.q.square_ref().complete()
+        {
+            return Err(Error::InvalidKey);
+        }
+
+        let lambda = (&self.p - Integer::ONE).complete() * (&self.q - Integer::ONE).complete();
+
+        if self.lambda != lambda || (&self.lambda * &self.mu).complete().modulo(&pk.n) != 1 {
+            return Err(Error::InvalidKey);
+        }
+
+        // L_p(g^(p-1) mod p^2) * h_p = 1 mod p, and the same modulo q
+        if Paillier::crt_half(&pk.g, &self.p, &self.p_square, &self.h_p) != 1
+            || Paillier::crt_half(&pk.g, &self.q, &self.q_square, &self.h_q) != 1
+            || (&self.q * &self.q_inv).complete().modulo(&self.p) != 1
+        {
+            return Err(Error::InvalidKey);
+        }
+
+        Ok(())
+    }
+}
+
+// The secret components are never printed
+impl fmt::Debug for PaillierSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaillierSecretKey").finish_non_exhaustive()
+    }
+}
+
+impl Drop for PaillierSecretKey {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.p);
+        zeroize_integer(&mut self.q);
+        zeroize_integer(&mut self.p_square);
+        zeroize_integer(&mut self.q_square);
+        zeroize_integer(&mut self.h_p);
+        zeroize_integer(&mut self.h_q);
+        zeroize_integer(&mut self.q_inv);
+        zeroize_integer(&mut self.lambda);
+        zeroize_integer(&mut self.mu);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{Complete, Integer};
+
+    use crate::{
+        error::Error,
+        paillier::{algorithms::Paillier, keys::PaillierPublicKey},
+        traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    fn public_key(n: Integer) -> PaillierPublicKey {
+        let n_square = n.square_ref().complete();
+        let g = (&n + Integer::ONE).complete();
+
+        PaillierPublicKey::new(n, n_square, g)
+    }
+
+    #[test]
+    fn paillier_public_key_validation_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (_, pk) = Paillier::keygen(80, &mut rng).unwrap();
+        assert!(pk.validate_partial().is_ok());
+        assert!(pk.validate().is_ok());
+
+        let n = pk.n.clone();
+        let invalid = [
+            PaillierPublicKey::new(n.clone(), n.clone(), (&n + Integer::ONE).complete()),
+            PaillierPublicKey::new(n.clone(), pk.n_square.clone(), n.clone()),
+            public_key((&n + Integer::ONE).complete()),
+            public_key(Integer::from(1) << 511u32),
+        ];
+
+        for pk in invalid {
+            assert_eq!(pk.validate_partial().unwrap_err(), Error::InvalidPublicKey);
+            assert_eq!(pk.validate().unwrap_err(), Error::InvalidPublicKey);
+        }
+
+        // Only the full validation detects small factors, primes and perfect powers
+        let multiple_of_three = (Integer::from(1) << 1100u32) * 3u32 + 3u32;
+        let perfect_square = ((Integer::from(1) << 600u32) + 1u32).square();
+        let prime = (Integer::from(1) << 1100u32).next_prime();
+
+        for n in [multiple_of_three, perfect_square, prime] {
+            let pk = public_key(n);
+
+            assert!(pk.validate_partial().is_ok());
+            assert_eq!(pk.validate().unwrap_err(), Error::InvalidPublicKey);
+        }
+    }
+
+    #[test]
+    fn paillier_key_pair_validation_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = Paillier::keygen(80, &mut rng).unwrap();
+        let (other_sk, other_pk) = Paillier::keygen(80, &mut rng).unwrap();
+
+        assert!(sk.validate_pair(&pk).is_ok());
+        assert!(other_sk.validate_pair(&other_pk).is_ok());
+
+        assert_eq!(sk.validate_pair(&other_pk).unwrap_err(), Error::InvalidKey);
+        assert_eq!(other_sk.validate_pair(&pk).unwrap_err(), Error::InvalidKey);
     }
 }
@@ -1,2 +1,5 @@
 pub mod algorithms;
-pub mod keys;
\ No newline at end of file
+mod blinding;
+pub mod encoding;
+pub mod keys;
+pub mod params;
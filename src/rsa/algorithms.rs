@@ -1,93 +1,221 @@
-use std::cmp::Ordering;
-
 use rug::{
-    integer::{IntegerExt64, IsPrime, Order},
+    integer::{IntegerExt64, Order},
     rand::RandState,
-    Assign, Complete, Integer,
+    Complete, Integer,
 };
 
-use crate::traits::public_enc::PublicEnc;
+use crate::{
+    error::Error,
+    traits::public_enc::PublicEnc,
+    utils::{
+        encoding, primes,
+        rand::{self, SecureRng},
+    },
+};
 
-use super::keys::{RSAPublicKey, RSASecretKey};
+use super::{
+    keys::{OtherPrimeInfo, RSAPublicKey, RSASecretKey},
+    params::RSAKeyParams,
+};
 
 pub struct RSA;
-const DEFAULT_E: u64 = 65537;
 
 impl RSA {
-    fn get_mod_bits(sec_level: u64) -> Result<u64, String> {
+    pub(crate) fn get_mod_bits(sec_level: u64) -> Result<u64, Error> {
         let mod_bits = match sec_level {
             80 => 1024,
             112 => 2048,
             128 => 3072,
             192 => 7680,
             256 => 15360,
-            _ => return Err("Invalid security level.".to_string()),
+            _ => return Err(Error::InvalidSecurityLevel),
         };
 
         Ok(mod_bits)
     }
-}
 
-impl PublicEnc for RSA {
-    type SecretKey = RSASecretKey;
-    type PublicKey = RSAPublicKey;
+    // Computes c^d mod N using the CRT attributes of the secret key.
+    // The base is blinded with a random r: (c * r^e)^d * r^-1 = c^d mod N,
+    // so the timing of the exponentiations is unrelated to c.
+    pub(crate) fn crt_pow(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        c: &Integer,
+        rng: &mut RandState,
+    ) -> Result<Integer, Error> {
+        if c.is_negative() || c >= &pk.n {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        let (c, r_inv) = sk.blinding.blind(pk, c, rng)?;
+        let m = RSA::crt_pow_unblinded(sk, &c)?;
+
+        // A fault in one CRT half gives m s.t. m^e = c mod p but not mod q,
+        // then gcd(m^e - c, N) = p (Bellcore attack). The result is
+        // re-encrypted and compared before it leaves this function.
+        if m.secure_pow_mod_ref(&pk.e, &pk.n).complete() != c {
+            return Err(Error::FaultDetected);
+        }
+
+        Ok((m * r_inv).modulo(&pk.n))
+    }
+
+    fn crt_pow_unblinded(sk: &RSASecretKey, c: &Integer) -> Result<Integer, Error> {
+        // secure_pow_mod requires a positive exponent
+        if sk.d_p <= 0 || sk.d_q <= 0 || sk.other_primes.iter().any(|r_i| r_i.d <= 0) {
+            return Err(Error::InvalidKey);
+        }
+
+        // m_p = (c mod p)^(d_p) mod p
+        let m_p = c
+            .modulo_ref(&sk.p)
+            .complete()
+            .secure_pow_mod(&sk.d_p, &sk.p);
+
+        // m_q = (c mod q)^(d_q) mod q
+        let m_q = c
+            .modulo_ref(&sk.q)
+            .complete()
+            .secure_pow_mod(&sk.d_q, &sk.q);
+
+        // m = m_q + ( (m_p - m_q) * (q_inv) mod p ) * q
+        let m = ((&m_p - &m_q).complete() * &sk.q_inv).modulo(&sk.p);
+        let mut m = &m * &sk.q + m_q;
+
+        // Garner's recombination of the other primes (RFC 8017, Section 5.1.2)
+        let mut r = (&sk.p * &sk.q).complete();
+
+        for r_i in &sk.other_primes {
+            // m_i = (c mod r_i)^(d_i) mod r_i
+            let m_i = c
+                .modulo_ref(&r_i.r)
+                .complete()
+                .secure_pow_mod(&r_i.d, &r_i.r);
+
+            // m = m + ( (m_i - m) * t_i mod r_i ) * R
+            let h = ((m_i - &m) * &r_i.t).modulo(&r_i.r);
+            m += h * &r;
+            r *= &r_i.r;
+        }
+
+        Ok(m)
+    }
+
+    // RSADP on a ciphertext of exactly k bytes, returning the k-byte encoded
+    // message EM with its leading zeros (RFC 8017, Sections 5.1.2 and 7.1.2)
+    pub(crate) fn decrypt_block<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        ciphertext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+
+        if ciphertext.len() != mod_bytes {
+            return Err(Error::CiphertextOutOfRange);
+        }
 
-    fn keygen(
+        let c = encoding::os2ip(ciphertext);
+        let m = rand::with_rand_state(rng, |rng| RSA::crt_pow(pk, sk, &c, rng))?;
+
+        encoding::i2osp(&m, mod_bytes)
+    }
+
+    // Largest number of primes for a modulus of mod_bits bits.
+    // Every prime must stay large enough to resist the elliptic curve
+    // factoring method, whose cost depends on the size of the smallest factor.
+    pub(crate) fn max_primes(mod_bits: u64) -> usize {
+        match mod_bits {
+            0..1024 => 2,
+            1024..4096 => 3,
+            4096..8192 => 4,
+            _ => 5,
+        }
+    }
+
+    // Generates a key whose modulus is the product of the given number of primes
+    pub fn keygen_multi_prime<R: SecureRng + ?Sized>(
         sec_level: u64,
+        primes: usize,
+        rng: &mut R,
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+        let params = RSAKeyParams::from_sec_level(sec_level)?.primes(primes);
+
+        RSA::keygen_with_params(&params, rng)
+    }
+
+    pub fn keygen_with_params<R: SecureRng + ?Sized>(
+        params: &RSAKeyParams,
+        rng: &mut R,
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+        rand::with_rand_state(rng, |rng| RSA::generate_key(params, rng))
+    }
+
+    fn generate_key(
+        params: &RSAKeyParams,
         rng: &mut RandState,
-    ) -> Result<(Self::SecretKey, Self::PublicKey), String> {
-        log::debug!("Generating a new key pair...");
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+        params.validate()?;
 
-        let mod_bits = RSA::get_mod_bits(sec_level)?;
-        let p_bits: u64 = mod_bits >> 1;
-        let q_bits: u64 = mod_bits - p_bits;
+        let e = params.e.clone();
+        let k = params.primes;
+        let mut factors: Vec<Integer> = Vec::with_capacity(k);
 
-        let e = Integer::from(DEFAULT_E);
-        let mut p: Integer = Integer::new();
-        let mut q: Integer = Integer::new();
+        let d = loop {
+            let mut remaining_bits = params.mod_bits;
 
-        // Generates p and q
-        let mut phi_n: Integer;
+            // Generates r_1, ..., r_k s.t. gcd(r_i - 1, e) = 1
+            for i in 0..k {
+                let r_bits = remaining_bits / (k - i) as u64;
+                remaining_bits -= r_bits;
 
-        loop {
-            loop {
-                p.assign(Integer::random_bits_64(p_bits, rng));
+                let rounds = params
+                    .mr_rounds
+                    .unwrap_or_else(|| primes::mr_rounds(r_bits));
+                let r = primes::random_prime_with(r_bits, rounds, rng, |r| {
+                    (r - Integer::ONE).complete().gcd(&e) == *Integer::ONE && !factors.contains(r)
+                })?;
 
-                if p.significant_bits_64() >= p_bits && p.is_probably_prime(12) == IsPrime::Probably
-                {
-                    break;
-                }
+                factors.push(r);
             }
 
-            // Generates q
-            loop {
-                q.assign(Integer::random_bits_64(q_bits, rng));
-
-                if q.significant_bits_64() >= q_bits && q.is_probably_prime(12) == IsPrime::Probably
-                {
-                    break;
+            // With more than two primes the top two bits of each one do not
+            // guarantee the size of the modulus
+            let n_bits = factors.iter().product::<Integer>().significant_bits_64();
+
+            if n_bits == params.mod_bits
+                && (!params.check_prime_distance || primes::are_far_apart(&factors))
+            {
+                // phi(n)
+                let phi_n: Integer = factors
+                    .iter()
+                    .map(|r| (r - Integer::ONE).complete())
+                    .product();
+
+                // Generates the private exponent
+                let d: Integer = match e.invert_ref(&phi_n) {
+                    Some(el) => el.complete(),
+                    None => return Err(Error::KeyGeneration),
+                };
+
+                // FIPS 186-5, Appendix A.1.1: the primes are generated again
+                // when d <= 2^(nlen / 2), which is very unlikely for a small e
+                if primes::d_is_large_enough(&d, params.mod_bits) {
+                    break d;
                 }
             }
 
-            // phi(n)
-            phi_n = (&p - Integer::ONE).complete() * (&q - Integer::ONE).complete();
-
-            let gcd = phi_n.gcd_ref(&e).complete();
+            factors.clear();
+        };
 
-            match gcd.cmp(Integer::ONE) {
-                Ordering::Equal => break,
-                _ => (),
-            }
-        }
+        let n: Integer = factors.iter().product();
 
-        // Generates the private exponent
-        let d: Integer = match e.invert_ref(&phi_n) {
-            Some(el) => el.complete(),
-            None => return Err("error while inverting the public exponent".to_string()),
+        let mut factors = factors.into_iter();
+        let (p, q) = match (factors.next(), factors.next()) {
+            (Some(p), Some(q)) => (p, q),
+            _ => return Err(Error::KeyGeneration),
         };
 
-        let n = (&p * &q).complete();
-
         // d_p = d mod (p - 1)
         let p_minus_one = (&p - Integer::ONE).complete();
         let d_p = d.modulo_ref(&p_minus_one).complete();
@@ -99,27 +227,55 @@ impl PublicEnc for RSA {
         // q_inv = q^-1 mod p
         let q_inv = match q.invert_ref(&p) {
             Some(val) => val.complete(),
-            None => return Err("Error while computing q_inv value.".to_string()),
+            None => return Err(Error::KeyGeneration),
         };
 
-        let pk = Self::PublicKey::new(n, e);
-        let sk = Self::SecretKey::new(p, q, d_p, d_q, q_inv);
+        // d_i = d mod (r_i - 1) and t_i = (r_1 * ... * r_(i-1))^-1 mod r_i
+        let mut other_primes = Vec::with_capacity(k - 2);
+        let mut r = (&p * &q).complete();
 
-        log::debug!("{:?}", sk);
+        for r_i in factors {
+            let d_i = d.modulo_ref(&(&r_i - Integer::ONE).complete()).complete();
+            let t_i = match r.invert_ref(&r_i) {
+                Some(val) => val.complete(),
+                None => return Err(Error::KeyGeneration),
+            };
+
+            r *= &r_i;
+            other_primes.push(OtherPrimeInfo::new(r_i, d_i, t_i));
+        }
+
+        let pk = RSAPublicKey::new(n, e);
+        let sk = RSASecretKey::new_multi_prime(p, q, d, d_p, d_q, q_inv, other_primes);
 
         Ok((sk, pk))
     }
+}
+
+impl PublicEnc for RSA {
+    type SecretKey = RSASecretKey;
+    type PublicKey = RSAPublicKey;
+
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(Self::SecretKey, Self::PublicKey), Error> {
+        log::debug!("Generating a new key pair...");
 
-    fn encrypt(
+        let params = RSAKeyParams::from_sec_level(sec_level)?;
+
+        RSA::keygen_with_params(&params, rng)
+    }
+
+    fn encrypt<R: SecureRng + ?Sized>(
         pk: &Self::PublicKey,
         plaintext: &[u8],
-        _rng: &mut RandState,
-    ) -> Result<Vec<u8>, String> {
-        let m = Integer::from_digits(plaintext, Order::MsfBe);
-        log::debug!("Encrypting the message: {}", m);
+        _rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let m = encoding::os2ip(plaintext);
 
-        if &m >= &pk.n || &m <= Integer::ONE {
-            return Err("The message is out of range.".to_string());
+        if m >= pk.n || &m <= Integer::ONE {
+            return Err(Error::MessageOutOfRange);
         }
 
         // The function pow_mod is not designed for cryptographic purposes
@@ -133,34 +289,24 @@ impl PublicEnc for RSA {
         // at the same position and the machine state is identical when starting.
         let c = m.secure_pow_mod_ref(&pk.e, &pk.n).complete();
 
-        let c: Vec<u8> = c.to_digits(Order::MsfBe);
-
-        Ok(c)
+        // The ciphertext is always as long as the modulus
+        encoding::i2osp(&c, encoding::byte_len(&pk.n))
     }
 
-    fn decrypt(
-        _pk: &Self::PublicKey,
+    fn decrypt<R: SecureRng + ?Sized>(
+        pk: &Self::PublicKey,
         sk: &Self::SecretKey,
         ciphertext: &[u8],
-    ) -> Result<Vec<u8>, String> {
-        let c = Integer::from_digits(ciphertext, Order::MsfBe);
-        log::debug!("Decrypting the ciphertext: {}", c);
-
-        // m_p = (c mod p)^(d_p) mod p
-        let m_p = match c.modulo_ref(&sk.p).complete().pow_mod(&sk.d_p, &sk.p) {
-            Ok(val) => val,
-            Err(_) => return Err("Error while computing m_p".to_string()),
-        };
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        if ciphertext.len() != encoding::byte_len(&pk.n) {
+            return Err(Error::CiphertextOutOfRange);
+        }
 
-        // m_q = (c mod q)^(d_q) mod q
-        let m_q = match c.modulo_ref(&sk.q).complete().pow_mod(&sk.d_q, &sk.q) {
-            Ok(val) => val,
-            Err(_) => return Err("Error while computing m_q".to_string()),
-        };
+        let c = encoding::os2ip(ciphertext);
 
-        // m = m_q + ( (m_p - m_q) * (q_inv) mod p ) * q
-        let m = ((&m_p - &m_q).complete() * &sk.q_inv).modulo(&sk.p);
-        let m = &m * &sk.q + m_q;
+        // The plaintext is the integer m, without leading zero bytes
+        let m = rand::with_rand_state(rng, |rng| RSA::crt_pow(pk, sk, &c, rng))?;
         let m = m.to_digits(Order::MsfBe);
 
         Ok(m)
@@ -169,29 +315,44 @@ impl PublicEnc for RSA {
 
 #[cfg(test)]
 mod test {
-    use rug::{integer::Order, rand::RandState};
+    use rug::{integer::Order, Complete, Integer};
 
     use crate::{
-        rsa::algorithms::RSA, traits::public_enc::PublicEnc, utils::rand::rug_randseed_os_rng,
+        error::Error,
+        rsa::{algorithms::RSA, keys::RSASecretKey},
+        traits::public_enc::PublicEnc,
+        utils::{
+            encoding, primes,
+            rand::{secure_rand_state, OsRandGen},
+        },
     };
 
     #[test]
     fn rsa_encrypt_failes_for_message_out_of_range() {
-        let mut rng = RandState::new();
-
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let mut rng = OsRandGen::new();
 
         let (_, pk) = RSA::keygen(80, &mut rng).unwrap();
 
         let m: Vec<u8> = pk.n.to_digits(Order::MsfBe);
-        assert!(RSA::encrypt(&pk, &m, &mut rng).is_err());
+        assert_eq!(
+            RSA::encrypt(&pk, &m, &mut rng),
+            Err(Error::MessageOutOfRange)
+        );
     }
 
     #[test]
-    fn rsa_encrypt_works_as_expected() {
-        let mut rng = RandState::new();
+    fn rsa_keygen_failes_for_invalid_security_level() {
+        let mut rng = OsRandGen::new();
 
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        assert_eq!(
+            RSA::keygen(100, &mut rng).unwrap_err(),
+            Error::InvalidSecurityLevel
+        );
+    }
+
+    #[test]
+    fn rsa_encrypt_works_as_expected() {
+        let mut rng = OsRandGen::new();
 
         let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
         let input = ["test1", "test2", "test3"];
@@ -199,9 +360,142 @@ mod test {
         for s in input {
             let m = s.as_bytes();
             let c = RSA::encrypt(&pk, m, &mut rng).unwrap();
+            assert_eq!(c.len(), encoding::byte_len(&pk.n));
 
-            let decrypted_message = RSA::decrypt(&pk, &sk, &c).unwrap();
+            let decrypted_message = RSA::decrypt(&pk, &sk, &c, &mut rng).unwrap();
             assert_eq!(m, &decrypted_message);
         }
     }
+
+    #[test]
+    fn rsa_ciphertexts_have_the_length_of_the_modulus() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let mod_bytes = encoding::byte_len(&pk.n);
+
+        // c = 2 has k - 1 leading zero bytes, which must survive the round trip
+        let c = encoding::i2osp(&Integer::from(2), mod_bytes).unwrap();
+        let m = RSA::decrypt_block(&pk, &sk, &c, &mut rng).unwrap();
+        assert_eq!(m.len(), mod_bytes);
+        assert_eq!(RSA::encrypt(&pk, &m, &mut rng).unwrap(), c);
+
+        assert_eq!(
+            RSA::decrypt(&pk, &sk, &c[1..], &mut rng).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+        assert_eq!(
+            RSA::decrypt_block(&pk, &sk, &[&[0], c.as_slice()].concat(), &mut rng).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+    }
+
+    #[test]
+    fn rsa_secret_key_debug_is_redacted() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, _) = RSA::keygen(80, &mut rng).unwrap();
+        let debug = format!("{:?}", sk);
+
+        assert_eq!(debug, "RSASecretKey { .. }");
+        assert!(!debug.contains(&sk.p.to_string()));
+    }
+
+    #[test]
+    fn rsa_blinded_decryption_matches_textbook_decryption() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let mut state = secure_rand_state();
+
+        // Enough iterations to go through a refresh of the blinding pair
+        for _ in 0..40 {
+            let c = pk.n.random_below_ref(&mut state).complete();
+            let expected = c.pow_mod_ref(&sk.d, &pk.n).unwrap().complete();
+
+            assert_eq!(RSA::crt_pow(&pk, &sk, &c, &mut state).unwrap(), expected);
+        }
+
+        assert_eq!(
+            RSA::crt_pow(&pk, &sk, &pk.n, &mut state).unwrap_err(),
+            Error::CiphertextOutOfRange
+        );
+    }
+
+    #[test]
+    fn rsa_decrypt_detects_faults_in_crt() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let c = RSA::encrypt(&pk, b"test1", &mut rng).unwrap();
+
+        // A wrong d_p stands for a fault in the exponentiation modulo p
+        let faulty = RSASecretKey::new(
+            sk.p.clone(),
+            sk.q.clone(),
+            sk.d.clone(),
+            (&sk.d_p + Integer::ONE).complete(),
+            sk.d_q.clone(),
+            sk.q_inv.clone(),
+        );
+
+        assert_eq!(
+            RSA::decrypt(&pk, &faulty, &c, &mut rng),
+            Err(Error::FaultDetected)
+        );
+        assert_eq!(RSA::decrypt(&pk, &sk, &c, &mut rng).unwrap(), b"test1");
+
+        // Without the check, the faulty result reveals the factor q of N
+        let c = encoding::os2ip(&c);
+        let m = RSA::crt_pow_unblinded(&faulty, &c).unwrap();
+
+        let m_e = m.pow_mod_ref(&pk.e, &pk.n).unwrap().complete();
+        assert_eq!((m_e - &c).gcd(&pk.n), sk.q);
+    }
+
+    #[test]
+    fn rsa_multi_prime_decryption_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen_multi_prime(80, 3, &mut rng).unwrap();
+        assert_eq!(sk.other_primes.len(), 1);
+
+        let r = &sk.other_primes[0];
+        assert_eq!((&sk.p * &sk.q).complete() * &r.r, pk.n);
+
+        let mut state = secure_rand_state();
+        for _ in 0..10 {
+            let c = pk.n.random_below_ref(&mut state).complete();
+            let expected = c.pow_mod_ref(&sk.d, &pk.n).unwrap().complete();
+
+            assert_eq!(RSA::crt_pow(&pk, &sk, &c, &mut state).unwrap(), expected);
+        }
+
+        let m = b"test1";
+        let c = RSA::encrypt(&pk, m, &mut rng).unwrap();
+        assert_eq!(RSA::decrypt(&pk, &sk, &c, &mut rng).unwrap(), m);
+    }
+
+    #[test]
+    fn rsa_multi_prime_keygen_failes_for_invalid_prime_count() {
+        let mut rng = OsRandGen::new();
+
+        // A 1024-bit modulus allows at most 3 primes
+        for primes in [0, 1, 4] {
+            assert_eq!(
+                RSA::keygen_multi_prime(80, primes, &mut rng).unwrap_err(),
+                Error::InvalidParameters
+            );
+        }
+    }
+
+    #[test]
+    fn rsa_close_primes_are_rejected() {
+        let p = (Integer::from(1) << 511u32) + 187u32;
+        let far = &p + (Integer::from(1) << 420u32);
+        let close = &p + (Integer::from(1) << 400u32);
+
+        assert!(primes::are_far_apart(&[p.clone(), far]));
+        assert!(!primes::are_far_apart(&[p, close]));
+    }
 }
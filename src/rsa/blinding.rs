@@ -0,0 +1,154 @@
+use std::sync::Mutex;
+
+use rug::{rand::RandState, Assign, Complete, Integer};
+
+use crate::{error::Error, utils::zeroize::zeroize_integer};
+
+use super::keys::RSAPublicKey;
+
+// Number of uses of a blinding pair before a fresh r is drawn
+const REFRESH_PERIOD: u32 = 32;
+
+// Blinding pair (r^e, r^-1) mod N, with the public key (N, e) it was drawn for
+struct Blinding {
+    n: Integer,
+    e: Integer,
+    r_e: Integer,
+    r_inv: Integer,
+    uses: u32,
+}
+
+impl Blinding {
+    fn new(pk: &RSAPublicKey, rng: &mut RandState) -> Result<Self, Error> {
+        if pk.e <= 0 {
+            return Err(Error::InvalidKey);
+        }
+
+        let mut r = Integer::new();
+
+        // Select a random 0 < r < N s.t. gcd(r,N) = 1
+        let r_inv = loop {
+            r.assign(pk.n.random_below_ref(rng));
+
+            if r.is_zero() {
+                continue;
+            }
+
+            if let Some(r_inv) = r.invert_ref(&pk.n) {
+                break r_inv.complete();
+            }
+        };
+
+        let r_e = r.secure_pow_mod_ref(&pk.e, &pk.n).complete();
+
+        zeroize_integer(&mut r);
+
+        Ok(Self {
+            n: pk.n.clone(),
+            e: pk.e.clone(),
+            r_e,
+            r_inv,
+            uses: 0,
+        })
+    }
+}
+
+impl Drop for Blinding {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.r_e);
+        zeroize_integer(&mut self.r_inv);
+    }
+}
+
+// Blinding pair cached in the secret key.
+// Drawing r and computing r^-1 costs about as much as the exponentiation it
+// protects, so the pair is squared after each use, (r^e)^2 = (r^2)^e, and
+// regenerated from the caller's RandState every REFRESH_PERIOD uses, or as soon
+// as it is asked for another public key.
+pub(crate) struct BlindingCache(Mutex<Option<Blinding>>);
+
+impl BlindingCache {
+    pub(crate) fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    // Returns c * r^e mod N and r^-1 mod N
+    pub(crate) fn blind(
+        &self,
+        pk: &RSAPublicKey,
+        c: &Integer,
+        rng: &mut RandState,
+    ) -> Result<(Integer, Integer), Error> {
+        let mut cache = match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let blinding = match cache.take() {
+            Some(b) if b.uses < REFRESH_PERIOD && b.n == pk.n && b.e == pk.e => b,
+            _ => Blinding::new(pk, rng)?,
+        };
+
+        let blinded = (c * &blinding.r_e).complete().modulo(&pk.n);
+        let r_inv = blinding.r_inv.clone();
+
+        // Never reuse the same pair twice
+        let next = Blinding {
+            n: blinding.n.clone(),
+            e: blinding.e.clone(),
+            r_e: blinding.r_e.square_ref().complete().modulo(&pk.n),
+            r_inv: blinding.r_inv.square_ref().complete().modulo(&pk.n),
+            uses: blinding.uses + 1,
+        };
+        *cache = Some(next);
+
+        Ok((blinded, r_inv))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{Complete, Integer};
+
+    use crate::{
+        rsa::algorithms::RSA,
+        traits::public_enc::PublicEnc,
+        utils::rand::{secure_rand_state, OsRandGen},
+    };
+
+    #[test]
+    fn blinding_pair_is_squared_between_uses() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let one = Integer::from(1);
+        let mut state = secure_rand_state();
+
+        let (r_e, r_inv) = sk.blinding.blind(&pk, &one, &mut state).unwrap();
+        // (r^e)^d * r^-1 = r * r^-1 = 1 mod N
+        let r = r_e.pow_mod_ref(&sk.d, &pk.n).unwrap().complete();
+        assert_eq!((r * &r_inv).modulo(&pk.n), 1);
+
+        let (r_e2, r_inv2) = sk.blinding.blind(&pk, &one, &mut state).unwrap();
+        assert_eq!(r_e2, r_e.square_ref().complete().modulo(&pk.n));
+        assert_eq!(r_inv2, r_inv.square_ref().complete().modulo(&pk.n));
+    }
+
+    #[test]
+    fn blinding_pair_follows_the_public_key() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let (other_sk, other_pk) = RSA::keygen(80, &mut rng).unwrap();
+        let one = Integer::from(1);
+        let mut state = secure_rand_state();
+
+        // Each pair must be valid for the key it is requested with:
+        // (r^e)^d * r^-1 = 1 mod N
+        for (d, pk) in [(&sk.d, &pk), (&other_sk.d, &other_pk), (&sk.d, &pk)] {
+            let (r_e, r_inv) = sk.blinding.blind(pk, &one, &mut state).unwrap();
+            let r = r_e.pow_mod_ref(d, &pk.n).unwrap().complete();
+            assert_eq!((r * &r_inv).modulo(&pk.n), 1);
+        }
+    }
+}
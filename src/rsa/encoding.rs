@@ -0,0 +1,441 @@
+// PKCS#1 (RFC 8017, Appendix A.1), SubjectPublicKeyInfo (RFC 5280) and
+// PKCS#8 (RFC 5208) encodings of the RSA keys, in both DER and PEM form.
+use pkcs1::{
+    der::{
+        asn1::{BitStringRef, UintRef},
+        pem::{self, LineEnding, PemLabel},
+        Decode, Encode,
+    },
+    RsaPrivateKey, RsaPublicKey,
+};
+use pkcs8::{spki::SubjectPublicKeyInfoRef, PrivateKeyInfo};
+use rug::{integer::Order, Integer};
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+
+use super::keys::{OtherPrimeInfo, RSAPublicKey, RSASecretKey};
+
+const SPKI_PEM_LABEL: &str = "PUBLIC KEY";
+const PKCS8_PEM_LABEL: &str = "PRIVATE KEY";
+
+fn to_uint(x: &Integer) -> Vec<u8> {
+    x.to_digits(Order::MsfBe)
+}
+
+// Big-endian bytes of a secret component, wiped when dropped
+fn to_secret_uint(x: &Integer) -> Zeroizing<Vec<u8>> {
+    Zeroizing::new(to_uint(x))
+}
+
+fn from_uint(x: UintRef) -> Integer {
+    Integer::from_digits(x.as_bytes(), Order::MsfBe)
+}
+
+// The DER may hold a secret key, so it is wiped once decoded
+fn pem_decode(label: &str, pem: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let (pem_label, der) = pem::decode_vec(pem.as_bytes()).map_err(|_| Error::Deserialization)?;
+    let der = Zeroizing::new(der);
+
+    if pem_label != label {
+        return Err(Error::Deserialization);
+    }
+
+    Ok(der)
+}
+
+fn pem_encode(label: &str, der: &[u8]) -> Result<String, Error> {
+    pem::encode_string(label, LineEnding::LF, der).map_err(|_| Error::Serialization)
+}
+
+fn secret_pem_encode(label: &str, der: &[u8]) -> Result<Zeroizing<String>, Error> {
+    pem_encode(label, der).map(Zeroizing::new)
+}
+
+impl RSAPublicKey {
+    // PKCS#1 RSAPublicKey
+    pub fn to_pkcs1_der(&self) -> Result<Vec<u8>, Error> {
+        let n = to_uint(&self.n);
+        let e = to_uint(&self.e);
+
+        let key = RsaPublicKey {
+            modulus: UintRef::new(&n).map_err(|_| Error::Serialization)?,
+            public_exponent: UintRef::new(&e).map_err(|_| Error::Serialization)?,
+        };
+
+        key.to_der().map_err(|_| Error::Serialization)
+    }
+
+    pub fn from_pkcs1_der(der: &[u8]) -> Result<Self, Error> {
+        let key = RsaPublicKey::from_der(der).map_err(|_| Error::Deserialization)?;
+
+        Ok(Self::new(
+            from_uint(key.modulus),
+            from_uint(key.public_exponent),
+        ))
+    }
+
+    pub fn to_pkcs1_pem(&self) -> Result<String, Error> {
+        pem_encode(RsaPublicKey::PEM_LABEL, &self.to_pkcs1_der()?)
+    }
+
+    pub fn from_pkcs1_pem(pem: &str) -> Result<Self, Error> {
+        Self::from_pkcs1_der(&pem_decode(RsaPublicKey::PEM_LABEL, pem)?)
+    }
+
+    // X.509 SubjectPublicKeyInfo wrapping the PKCS#1 RSAPublicKey
+    pub fn to_public_key_der(&self) -> Result<Vec<u8>, Error> {
+        let key = self.to_pkcs1_der()?;
+
+        let spki = SubjectPublicKeyInfoRef {
+            algorithm: pkcs1::ALGORITHM_ID,
+            subject_public_key: BitStringRef::from_bytes(&key).map_err(|_| Error::Serialization)?,
+        };
+
+        spki.to_der().map_err(|_| Error::Serialization)
+    }
+
+    pub fn from_public_key_der(der: &[u8]) -> Result<Self, Error> {
+        let spki = SubjectPublicKeyInfoRef::from_der(der).map_err(|_| Error::Deserialization)?;
+
+        if spki.algorithm.oid != pkcs1::ALGORITHM_OID {
+            return Err(Error::Deserialization);
+        }
+
+        match spki.subject_public_key.as_bytes() {
+            Some(key) => Self::from_pkcs1_der(key),
+            None => Err(Error::Deserialization),
+        }
+    }
+
+    pub fn to_public_key_pem(&self) -> Result<String, Error> {
+        pem_encode(SPKI_PEM_LABEL, &self.to_public_key_der()?)
+    }
+
+    pub fn from_public_key_pem(pem: &str) -> Result<Self, Error> {
+        Self::from_public_key_der(&pem_decode(SPKI_PEM_LABEL, pem)?)
+    }
+}
+
+impl RSASecretKey {
+    // PKCS#1 RSAPrivateKey. The modulus and the public exponent are taken from pk.
+    // The encoding and the intermediate buffers are wiped when dropped.
+    pub fn to_pkcs1_der(&self, pk: &RSAPublicKey) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let n = to_uint(&pk.n);
+        let e = to_uint(&pk.e);
+        let d = to_secret_uint(&self.d);
+        let p = to_secret_uint(&self.p);
+        let q = to_secret_uint(&self.q);
+        let d_p = to_secret_uint(&self.d_p);
+        let d_q = to_secret_uint(&self.d_q);
+        let q_inv = to_secret_uint(&self.q_inv);
+        let other_primes: Vec<[Zeroizing<Vec<u8>>; 3]> = self
+            .other_primes
+            .iter()
+            .map(|r_i| {
+                [
+                    to_secret_uint(&r_i.r),
+                    to_secret_uint(&r_i.d),
+                    to_secret_uint(&r_i.t),
+                ]
+            })
+            .collect();
+
+        let uint = |x| UintRef::new(x).map_err(|_| Error::Serialization);
+
+        // The version is derived from the presence of otherPrimeInfos
+        let other_prime_infos = if other_primes.is_empty() {
+            None
+        } else {
+            let infos = other_primes.iter().map(|[r, d, t]| {
+                Ok(pkcs1::OtherPrimeInfo {
+                    prime: uint(r)?,
+                    exponent: uint(d)?,
+                    coefficient: uint(t)?,
+                })
+            });
+
+            Some(infos.collect::<Result<Vec<_>, Error>>()?)
+        };
+
+        let key = RsaPrivateKey {
+            modulus: uint(&n)?,
+            public_exponent: uint(&e)?,
+            private_exponent: uint(&d)?,
+            prime1: uint(&p)?,
+            prime2: uint(&q)?,
+            exponent1: uint(&d_p)?,
+            exponent2: uint(&d_q)?,
+            coefficient: uint(&q_inv)?,
+            other_prime_infos,
+        };
+
+        key.to_der()
+            .map(Zeroizing::new)
+            .map_err(|_| Error::Serialization)
+    }
+
+    // The decoded key pair must pass RSAPublicKey::validate and validate_pair
+    pub fn from_pkcs1_der(der: &[u8]) -> Result<(Self, RSAPublicKey), Error> {
+        let key = RsaPrivateKey::from_der(der).map_err(|_| Error::Deserialization)?;
+
+        let other_primes = key
+            .other_prime_infos
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r_i| {
+                OtherPrimeInfo::new(
+                    from_uint(r_i.prime),
+                    from_uint(r_i.exponent),
+                    from_uint(r_i.coefficient),
+                )
+            })
+            .collect();
+
+        let pk = RSAPublicKey::new(from_uint(key.modulus), from_uint(key.public_exponent));
+        let sk = Self::new_multi_prime(
+            from_uint(key.prime1),
+            from_uint(key.prime2),
+            from_uint(key.private_exponent),
+            from_uint(key.exponent1),
+            from_uint(key.exponent2),
+            from_uint(key.coefficient),
+            other_primes,
+        );
+
+        pk.validate()?;
+        sk.validate_pair(&pk)?;
+
+        Ok((sk, pk))
+    }
+
+    pub fn to_pkcs1_pem(&self, pk: &RSAPublicKey) -> Result<Zeroizing<String>, Error> {
+        secret_pem_encode(RsaPrivateKey::PEM_LABEL, &self.to_pkcs1_der(pk)?)
+    }
+
+    pub fn from_pkcs1_pem(pem: &str) -> Result<(Self, RSAPublicKey), Error> {
+        Self::from_pkcs1_der(&pem_decode(RsaPrivateKey::PEM_LABEL, pem)?)
+    }
+
+    // PKCS#8 PrivateKeyInfo wrapping the PKCS#1 RSAPrivateKey
+    pub fn to_pkcs8_der(&self, pk: &RSAPublicKey) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let key = self.to_pkcs1_der(pk)?;
+        let info = PrivateKeyInfo::new(pkcs1::ALGORITHM_ID, &key);
+
+        info.to_der()
+            .map(Zeroizing::new)
+            .map_err(|_| Error::Serialization)
+    }
+
+    pub fn from_pkcs8_der(der: &[u8]) -> Result<(Self, RSAPublicKey), Error> {
+        let info = PrivateKeyInfo::from_der(der).map_err(|_| Error::Deserialization)?;
+
+        if info.algorithm.oid != pkcs1::ALGORITHM_OID {
+            return Err(Error::Deserialization);
+        }
+
+        Self::from_pkcs1_der(info.private_key)
+    }
+
+    pub fn to_pkcs8_pem(&self, pk: &RSAPublicKey) -> Result<Zeroizing<String>, Error> {
+        secret_pem_encode(PKCS8_PEM_LABEL, &self.to_pkcs8_der(pk)?)
+    }
+
+    pub fn from_pkcs8_pem(pem: &str) -> Result<(Self, RSAPublicKey), Error> {
+        Self::from_pkcs8_der(&pem_decode(PKCS8_PEM_LABEL, pem)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{Complete, Integer};
+    use sha2::Sha256;
+
+    use crate::{
+        error::Error,
+        rsa::{
+            algorithms::RSA,
+            keys::{RSAPublicKey, RSASecretKey},
+        },
+        rsaoaep::algorithms::RSAOAEP,
+        traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    const PKCS1_DER: &[u8] = include_bytes!("../../tests/fixtures/rsa2048-pkcs1.der");
+    const PKCS1_PEM: &str = include_str!("../../tests/fixtures/rsa2048-pkcs1.pem");
+    const PKCS8_DER: &[u8] = include_bytes!("../../tests/fixtures/rsa2048-pkcs8.der");
+    const PKCS8_PEM: &str = include_str!("../../tests/fixtures/rsa2048-pkcs8.pem");
+    const PUB_PKCS1_DER: &[u8] = include_bytes!("../../tests/fixtures/rsa2048-pub-pkcs1.der");
+    const PUB_PKCS1_PEM: &str = include_str!("../../tests/fixtures/rsa2048-pub-pkcs1.pem");
+    const SPKI_DER: &[u8] = include_bytes!("../../tests/fixtures/rsa2048-spki.der");
+    const SPKI_PEM: &str = include_str!("../../tests/fixtures/rsa2048-spki.pem");
+    const OAEP_CIPHERTEXT: &[u8] = include_bytes!("../../tests/fixtures/rsa2048-oaep-sha256.bin");
+    const THREE_PRIME_PEM: &str = include_str!("../../tests/fixtures/rsa2048-3prime-pkcs1.pem");
+    const THREE_PRIME_OAEP_CIPHERTEXT: &[u8] =
+        include_bytes!("../../tests/fixtures/rsa2048-3prime-oaep-sha256.bin");
+
+    fn assert_same_keys(a: &(RSASecretKey, RSAPublicKey), b: &(RSASecretKey, RSAPublicKey)) {
+        assert_eq!(a.1.n, b.1.n);
+        assert_eq!(a.1.e, b.1.e);
+        assert_eq!(a.0.p, b.0.p);
+        assert_eq!(a.0.q, b.0.q);
+        assert_eq!(a.0.d, b.0.d);
+        assert_eq!(a.0.d_p, b.0.d_p);
+        assert_eq!(a.0.d_q, b.0.d_q);
+        assert_eq!(a.0.q_inv, b.0.q_inv);
+        assert_eq!(a.0.other_primes.len(), b.0.other_primes.len());
+
+        for (r_a, r_b) in a.0.other_primes.iter().zip(&b.0.other_primes) {
+            assert_eq!(r_a.r, r_b.r);
+            assert_eq!(r_a.d, r_b.d);
+            assert_eq!(r_a.t, r_b.t);
+        }
+    }
+
+    #[test]
+    fn rsa_openssl_keys_are_decoded() {
+        let (sk, pk) = RSASecretKey::from_pkcs1_der(PKCS1_DER).unwrap();
+
+        assert_eq!(pk.n.significant_bits(), 2048);
+        assert_eq!(pk.e, 65537);
+        assert_eq!((&sk.p * &sk.q).complete(), pk.n);
+        assert!(pk.validate().is_ok());
+        assert!(sk.validate_pair(&pk).is_ok());
+
+        let keys = (sk, pk);
+        assert_same_keys(&keys, &RSASecretKey::from_pkcs1_pem(PKCS1_PEM).unwrap());
+        assert_same_keys(&keys, &RSASecretKey::from_pkcs8_der(PKCS8_DER).unwrap());
+        assert_same_keys(&keys, &RSASecretKey::from_pkcs8_pem(PKCS8_PEM).unwrap());
+
+        for pk in [
+            RSAPublicKey::from_pkcs1_der(PUB_PKCS1_DER).unwrap(),
+            RSAPublicKey::from_pkcs1_pem(PUB_PKCS1_PEM).unwrap(),
+            RSAPublicKey::from_public_key_der(SPKI_DER).unwrap(),
+            RSAPublicKey::from_public_key_pem(SPKI_PEM).unwrap(),
+        ] {
+            assert_eq!(pk.n, keys.1.n);
+            assert_eq!(pk.e, keys.1.e);
+        }
+    }
+
+    #[test]
+    fn rsa_openssl_keys_are_encoded_byte_for_byte() {
+        let (sk, pk) = RSASecretKey::from_pkcs1_der(PKCS1_DER).unwrap();
+
+        assert_eq!(*sk.to_pkcs1_der(&pk).unwrap(), PKCS1_DER);
+        assert_eq!(*sk.to_pkcs1_pem(&pk).unwrap(), PKCS1_PEM);
+        assert_eq!(*sk.to_pkcs8_der(&pk).unwrap(), PKCS8_DER);
+        assert_eq!(*sk.to_pkcs8_pem(&pk).unwrap(), PKCS8_PEM);
+        assert_eq!(pk.to_pkcs1_der().unwrap(), PUB_PKCS1_DER);
+        assert_eq!(pk.to_pkcs1_pem().unwrap(), PUB_PKCS1_PEM);
+        assert_eq!(pk.to_public_key_der().unwrap(), SPKI_DER);
+        assert_eq!(pk.to_public_key_pem().unwrap(), SPKI_PEM);
+    }
+
+    #[test]
+    fn rsa_openssl_key_decrypts_openssl_ciphertext() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSASecretKey::from_pkcs8_pem(PKCS8_PEM).unwrap();
+
+        let m = RSAOAEP::<Sha256>::decrypt(&pk, &sk, OAEP_CIPHERTEXT, &mut rng).unwrap();
+        assert_eq!(m, b"pke_rust fixture");
+    }
+
+    #[test]
+    fn rsa_generated_keys_round_trip() {
+        let mut rng = OsRandGen::new();
+
+        let keys = RSA::keygen(80, &mut rng).unwrap();
+        let (sk, pk) = &keys;
+
+        let decoded = RSASecretKey::from_pkcs1_pem(&sk.to_pkcs1_pem(pk).unwrap()).unwrap();
+        assert_same_keys(&keys, &decoded);
+
+        let decoded = RSASecretKey::from_pkcs8_der(&sk.to_pkcs8_der(pk).unwrap()).unwrap();
+        assert_same_keys(&keys, &decoded);
+
+        let decoded = RSAPublicKey::from_public_key_pem(&pk.to_public_key_pem().unwrap()).unwrap();
+        assert_eq!(decoded.n, pk.n);
+        assert_eq!(decoded.e, pk.e);
+
+        // The private exponent is consistent with the CRT attributes
+        let p_minus_one = (&sk.p - Integer::ONE).complete();
+        assert_eq!(sk.d.modulo_ref(&p_minus_one).complete(), sk.d_p);
+    }
+
+    #[test]
+    fn rsa_multi_prime_keys_round_trip() {
+        let mut rng = OsRandGen::new();
+
+        // Key generated with openssl genpkey -pkeyopt rsa_keygen_primes:3
+        let (sk, pk) = RSASecretKey::from_pkcs1_pem(THREE_PRIME_PEM).unwrap();
+        assert_eq!(sk.other_primes.len(), 1);
+        assert_eq!(*sk.to_pkcs1_pem(&pk).unwrap(), THREE_PRIME_PEM);
+        assert!(sk.validate_pair(&pk).is_ok());
+
+        let m =
+            RSAOAEP::<Sha256>::decrypt(&pk, &sk, THREE_PRIME_OAEP_CIPHERTEXT, &mut rng).unwrap();
+        assert_eq!(m, b"pke_rust fixture");
+
+        let keys = RSA::keygen_multi_prime(80, 3, &mut rng).unwrap();
+        let (sk, pk) = &keys;
+
+        let decoded = RSASecretKey::from_pkcs8_pem(&sk.to_pkcs8_pem(pk).unwrap()).unwrap();
+        assert_same_keys(&keys, &decoded);
+    }
+
+    #[test]
+    fn rsa_decoding_rejects_wrong_formats() {
+        assert_eq!(
+            RSASecretKey::from_pkcs1_der(PKCS8_DER).unwrap_err(),
+            Error::Deserialization
+        );
+        assert_eq!(
+            RSASecretKey::from_pkcs8_pem(PKCS1_PEM).unwrap_err(),
+            Error::Deserialization
+        );
+        assert_eq!(
+            RSAPublicKey::from_public_key_der(PUB_PKCS1_DER).unwrap_err(),
+            Error::Deserialization
+        );
+        assert_eq!(
+            RSAPublicKey::from_pkcs1_pem(SPKI_PEM).unwrap_err(),
+            Error::Deserialization
+        );
+        assert_eq!(
+            RSAPublicKey::from_pkcs1_der(&PUB_PKCS1_DER[1..]).unwrap_err(),
+            Error::Deserialization
+        );
+    }
+
+    #[test]
+    fn rsa_decoding_rejects_inconsistent_keys() {
+        let (sk, pk) = RSASecretKey::from_pkcs1_der(PKCS1_DER).unwrap();
+
+        // d_q is off by 2, the other attributes are untouched
+        let bad_sk = RSASecretKey::new(
+            sk.p.clone(),
+            sk.q.clone(),
+            sk.d.clone(),
+            sk.d_p.clone(),
+            (&sk.d_q + 2u32).complete(),
+            sk.q_inv.clone(),
+        );
+
+        let der = bad_sk.to_pkcs8_der(&pk).unwrap();
+        assert_eq!(
+            RSASecretKey::from_pkcs8_der(&der).unwrap_err(),
+            Error::InvalidKey
+        );
+
+        // Even modulus
+        let bad_pk = RSAPublicKey::new((&pk.n + 1u32).complete(), pk.e.clone());
+        let pem = sk.to_pkcs1_pem(&bad_pk).unwrap();
+        assert_eq!(
+            RSASecretKey::from_pkcs1_pem(&pem).unwrap_err(),
+            Error::InvalidPublicKey
+        );
+    }
+}
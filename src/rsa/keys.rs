@@ -1,4 +1,13 @@
-use rug::Integer;
+use std::fmt;
+
+use rug::{Complete, Integer};
+
+use crate::{
+    error::Error,
+    utils::{primes, zeroize::zeroize_integer},
+};
+
+use super::{blinding::BlindingCache, params::MIN_MOD_BITS};
 
 // Represents the RSA's public key
 #[derive(Debug)]
@@ -10,32 +19,253 @@ pub struct RSAPublicKey {
 }
 
 // Represents the RSA's secret key
-#[derive(Debug)]
 pub struct RSASecretKey {
     // Prime factor of N
     pub p: Integer,
     pub q: Integer,
 
+    // Private exponent
+    pub d: Integer,
+
     // CRT attributes
     pub d_p: Integer,
     pub d_q: Integer,
     pub q_inv: Integer,
+
+    // Additional primes of a multi-prime key (RFC 8017, Section 3.2),
+    // empty for a two-prime key
+    pub other_primes: Vec<OtherPrimeInfo>,
+
+    // Cached blinding pair used by decryption and signing
+    pub(crate) blinding: BlindingCache,
+}
+
+// Represents the i-th prime of a multi-prime key, for i >= 3
+pub struct OtherPrimeInfo {
+    // Prime factor r_i of N
+    pub r: Integer,
+    // CRT exponent d_i = d mod (r_i - 1)
+    pub d: Integer,
+    // CRT coefficient t_i = (r_1 * r_2 * ... * r_(i-1))^-1 mod r_i
+    pub t: Integer,
 }
 
 impl RSAPublicKey {
     pub fn new(n: Integer, e: Integer) -> Self {
         Self { n, e }
     }
+
+    // Cheap checks for a key received from a peer: N is odd and at least
+    // MIN_MOD_BITS bits long, e is odd and 3 <= e < min(N, 2^256)
+    pub fn validate_partial(&self) -> Result<(), Error> {
+        if self.n.significant_bits() < MIN_MOD_BITS as u32 || self.n.is_even() {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        if self.e < 3 || self.e.is_even() || self.e >= self.n || self.e.significant_bits() > 256 {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        Ok(())
+    }
+
+    // Partial checks, plus N is neither a prime, a perfect power nor a
+    // multiple of a small prime (SP 800-89, Section 5.3.3)
+    pub fn validate(&self) -> Result<(), Error> {
+        self.validate_partial()?;
+
+        if primes::has_small_factor(&self.n)
+            || primes::is_prime(&self.n)
+            || self.n.is_perfect_power()
+        {
+            return Err(Error::InvalidPublicKey);
+        }
+
+        Ok(())
+    }
 }
 
 impl RSASecretKey {
-    pub fn new(p: Integer, q: Integer, d_p: Integer, d_q: Integer, q_inv: Integer) -> Self {
+    pub fn new(
+        p: Integer,
+        q: Integer,
+        d: Integer,
+        d_p: Integer,
+        d_q: Integer,
+        q_inv: Integer,
+    ) -> Self {
         Self {
             p,
             q,
+            d,
             d_p,
             d_q,
             q_inv,
+            other_primes: Vec::new(),
+            blinding: BlindingCache::new(),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_multi_prime(
+        p: Integer,
+        q: Integer,
+        d: Integer,
+        d_p: Integer,
+        d_q: Integer,
+        q_inv: Integer,
+        other_primes: Vec<OtherPrimeInfo>,
+    ) -> Self {
+        let mut sk = Self::new(p, q, d, d_p, d_q, q_inv);
+        sk.other_primes = other_primes;
+
+        sk
+    }
+
+    // Checks that the secret key belongs to pk: N is the product of the
+    // primes, e * d = 1 modulo every r_i - 1 and the CRT attributes are
+    // derived from d and the primes
+    pub fn validate_pair(&self, pk: &RSAPublicKey) -> Result<(), Error> {
+        let product = self
+            .other_primes
+            .iter()
+            .fold((&self.p * &self.q).complete(), |acc, info| acc * &info.r);
+
+        if product != pk.n {
+            return Err(Error::InvalidKey);
+        }
+
+        // (r, d mod (r - 1)) for every prime
+        let mut exponents = vec![(&self.p, &self.d_p), (&self.q, &self.d_q)];
+        exponents.extend(self.other_primes.iter().map(|info| (&info.r, &info.d)));
+
+        for (r, d_r) in exponents {
+            let r_minus_one = (r - Integer::ONE).complete();
+
+            if self.d.modulo_ref(&r_minus_one).complete() != *d_r
+                || (&pk.e * d_r).complete().modulo(&r_minus_one) != 1
+            {
+                return Err(Error::InvalidKey);
+            }
+        }
+
+        // q * q_inv = 1 mod p and (r_1 * ... * r_(i-1)) * t_i = 1 mod r_i
+        if (&self.q * &self.q_inv).complete().modulo(&self.p) != 1 {
+            return Err(Error::InvalidKey);
+        }
+
+        let mut r = (&self.p * &self.q).complete();
+
+        for info in &self.other_primes {
+            if (&r * &info.t).complete().modulo(&info.r) != 1 {
+                return Err(Error::InvalidKey);
+            }
+
+            r *= &info.r;
+        }
+
+        Ok(())
+    }
+}
+
+impl OtherPrimeInfo {
+    pub fn new(r: Integer, d: Integer, t: Integer) -> Self {
+        Self { r, d, t }
+    }
+}
+
+// The secret components are never printed
+impl fmt::Debug for RSASecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RSASecretKey").finish_non_exhaustive()
+    }
+}
+
+impl Drop for RSASecretKey {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.p);
+        zeroize_integer(&mut self.q);
+        zeroize_integer(&mut self.d);
+        zeroize_integer(&mut self.d_p);
+        zeroize_integer(&mut self.d_q);
+        zeroize_integer(&mut self.q_inv);
+    }
+}
+
+impl fmt::Debug for OtherPrimeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtherPrimeInfo").finish_non_exhaustive()
+    }
+}
+
+impl Drop for OtherPrimeInfo {
+    fn drop(&mut self) {
+        zeroize_integer(&mut self.r);
+        zeroize_integer(&mut self.d);
+        zeroize_integer(&mut self.t);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::Integer;
+
+    use crate::{
+        error::Error,
+        rsa::{algorithms::RSA, keys::RSAPublicKey},
+        traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    #[test]
+    fn rsa_public_key_validation_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (_, pk) = RSA::keygen(80, &mut rng).unwrap();
+        assert!(pk.validate_partial().is_ok());
+        assert!(pk.validate().is_ok());
+
+        let n = pk.n.clone();
+        let invalid = [
+            RSAPublicKey::new(n.clone(), Integer::from(1)),
+            RSAPublicKey::new(n.clone(), Integer::from(65536)),
+            RSAPublicKey::new(n.clone(), n.clone() + 2u32),
+            RSAPublicKey::new(n.clone() + 1u32, Integer::from(65537)),
+            RSAPublicKey::new(Integer::from(1) << 511u32, Integer::from(65537)),
+        ];
+
+        for pk in invalid {
+            assert_eq!(pk.validate_partial().unwrap_err(), Error::InvalidPublicKey);
+            assert_eq!(pk.validate().unwrap_err(), Error::InvalidPublicKey);
+        }
+
+        // Only the full validation detects small factors, primes and perfect powers
+        let multiple_of_three = (Integer::from(1) << 1100u32) * 3u32 + 3u32;
+        let perfect_square = ((Integer::from(1) << 600u32) + 1u32).square();
+        let prime = (Integer::from(1) << 1100u32).next_prime();
+
+        for n in [multiple_of_three, perfect_square, prime] {
+            let pk = RSAPublicKey::new(n, Integer::from(65537));
+
+            assert!(pk.validate_partial().is_ok());
+            assert_eq!(pk.validate().unwrap_err(), Error::InvalidPublicKey);
         }
     }
+
+    #[test]
+    fn rsa_key_pair_validation_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let (other_sk, other_pk) = RSA::keygen_multi_prime(80, 3, &mut rng).unwrap();
+
+        assert!(sk.validate_pair(&pk).is_ok());
+        assert!(other_sk.validate_pair(&other_pk).is_ok());
+
+        assert_eq!(sk.validate_pair(&other_pk).unwrap_err(), Error::InvalidKey);
+        assert_eq!(other_sk.validate_pair(&pk).unwrap_err(), Error::InvalidKey);
+
+        let wrong_e = RSAPublicKey::new(pk.n.clone(), Integer::from(3));
+        assert_eq!(sk.validate_pair(&wrong_e).unwrap_err(), Error::InvalidKey);
+    }
 }
@@ -0,0 +1,138 @@
+use rug::Integer;
+
+use crate::error::Error;
+
+use super::algorithms::RSA;
+
+const DEFAULT_E: u64 = 65537;
+
+// Smallest modulus accepted by the builder and by key validation
+pub(crate) const MIN_MOD_BITS: u64 = 1024;
+
+// Parameters of the RSA key generation.
+// RSAKeyParams::new(4096).public_exponent(Integer::from(3)).mr_rounds(20)
+#[derive(Debug, Clone)]
+pub struct RSAKeyParams {
+    pub(crate) mod_bits: u64,
+    pub(crate) e: Integer,
+    pub(crate) mr_rounds: Option<u32>,
+    pub(crate) check_prime_distance: bool,
+    pub(crate) primes: usize,
+}
+
+impl RSAKeyParams {
+    // Two-prime modulus of mod_bits bits, e = 65537, the FIPS 186-5 number of
+    // Miller-Rabin rounds for the size of the primes and the |p - q| check enabled
+    pub fn new(mod_bits: u64) -> Self {
+        Self {
+            mod_bits,
+            e: Integer::from(DEFAULT_E),
+            mr_rounds: None,
+            check_prime_distance: true,
+            primes: 2,
+        }
+    }
+
+    // Default parameters for one of the supported security levels
+    pub fn from_sec_level(sec_level: u64) -> Result<Self, Error> {
+        Ok(Self::new(RSA::get_mod_bits(sec_level)?))
+    }
+
+    pub fn public_exponent(mut self, e: Integer) -> Self {
+        self.e = e;
+        self
+    }
+
+    pub fn mr_rounds(mut self, rounds: u32) -> Self {
+        self.mr_rounds = Some(rounds);
+        self
+    }
+
+    // Rejects primes closer than 2^(|r| - 100), which would make N
+    // vulnerable to Fermat's factorization method (FIPS 186-5, A.1.3)
+    pub fn check_prime_distance(mut self, check: bool) -> Self {
+        self.check_prime_distance = check;
+        self
+    }
+
+    pub fn primes(mut self, primes: usize) -> Self {
+        self.primes = primes;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.mod_bits < MIN_MOD_BITS || self.mr_rounds == Some(0) {
+            return Err(Error::InvalidParameters);
+        }
+
+        // e must be odd and 3 <= e < 2^256
+        if self.e < 3 || self.e.is_even() || self.e.significant_bits() > 256 {
+            return Err(Error::InvalidParameters);
+        }
+
+        if self.primes < 2 || self.primes > RSA::max_primes(self.mod_bits) {
+            return Err(Error::InvalidParameters);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::{Complete, Integer};
+
+    use crate::{
+        error::Error,
+        rsa::{algorithms::RSA, params::RSAKeyParams},
+        traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    #[test]
+    fn rsa_key_params_are_validated() {
+        assert!(RSAKeyParams::new(4096).validate().is_ok());
+        assert!(RSAKeyParams::from_sec_level(112)
+            .unwrap()
+            .validate()
+            .is_ok());
+
+        assert_eq!(
+            RSAKeyParams::from_sec_level(100).unwrap_err(),
+            Error::InvalidSecurityLevel
+        );
+
+        let invalid = [
+            RSAKeyParams::new(512),
+            RSAKeyParams::new(2048).public_exponent(Integer::from(1)),
+            RSAKeyParams::new(2048).public_exponent(Integer::from(65536)),
+            RSAKeyParams::new(2048).public_exponent(Integer::from(1) << 256),
+            RSAKeyParams::new(2048).mr_rounds(0),
+            RSAKeyParams::new(2048).primes(1),
+            RSAKeyParams::new(2048).primes(4),
+        ];
+
+        for params in invalid {
+            assert_eq!(params.validate().unwrap_err(), Error::InvalidParameters);
+        }
+    }
+
+    #[test]
+    fn rsa_keygen_with_custom_params_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let params = RSAKeyParams::new(1536)
+            .public_exponent(Integer::from(3))
+            .mr_rounds(20)
+            .check_prime_distance(false);
+        let (sk, pk) = RSA::keygen_with_params(&params, &mut rng).unwrap();
+
+        assert_eq!(pk.e, 3);
+        assert_eq!(pk.n.significant_bits(), 1536);
+        assert_eq!((&sk.p * &sk.q).complete(), pk.n);
+
+        let m = b"test1";
+        let c = RSA::encrypt(&pk, m, &mut rng).unwrap();
+        assert_eq!(RSA::decrypt(&pk, &sk, &c, &mut rng).unwrap(), m);
+    }
+}
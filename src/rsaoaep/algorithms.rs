@@ -0,0 +1,263 @@
+use std::marker::PhantomData;
+
+use digest::Digest;
+use sha2::Sha256;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::{
+    error::Error,
+    rsa::{
+        algorithms::RSA,
+        keys::{RSAPublicKey, RSASecretKey},
+    },
+    traits::public_enc::PublicEnc,
+    utils::{encoding, mgf::mgf1, rand::SecureRng},
+};
+
+// RSAES-OAEP (RFC 8017, Section 7.1).
+// H is the hash function applied to the label, MGFH the one used by MGF1.
+pub struct RSAOAEP<H = Sha256, MGFH = H> {
+    _hash: PhantomData<(H, MGFH)>,
+}
+
+impl<H: Digest, MGFH: Digest> RSAOAEP<H, MGFH> {
+    fn oaep_encode<R: SecureRng + ?Sized>(
+        mod_bytes: usize,
+        plaintext: &[u8],
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let h_len = <H as Digest>::output_size();
+
+        if mod_bytes < 2 * h_len + 2 {
+            return Err(Error::ModulusTooShort);
+        }
+
+        if plaintext.len() > mod_bytes - 2 * h_len - 2 {
+            return Err(Error::MessageTooLong);
+        }
+
+        // DB = lHash || PS || 0x01 || M
+        let db_len = mod_bytes - h_len - 1;
+        let mut db: Vec<u8> = Vec::with_capacity(db_len);
+        db.extend_from_slice(&H::digest(label));
+        db.resize(db_len - plaintext.len() - 1, 0);
+        db.push(1);
+        db.extend_from_slice(plaintext);
+
+        let mut seed: Vec<u8> = vec![0; h_len];
+        rng.fill_bytes(&mut seed);
+
+        let db_mask = mgf1::<MGFH>(&seed, db_len);
+        db.iter_mut().zip(db_mask).for_each(|(x, y)| *x ^= y);
+
+        let seed_mask = mgf1::<MGFH>(&db, h_len);
+        seed.iter_mut().zip(seed_mask).for_each(|(x, y)| *x ^= y);
+
+        // EM = 0x00 || maskedSeed || maskedDB
+        let mut encoded: Vec<u8> = Vec::with_capacity(mod_bytes);
+        encoded.push(0);
+        encoded.extend(seed);
+        encoded.extend(db);
+
+        Ok(encoded)
+    }
+
+    // The checks on the encoded message run in constant time and every failure
+    // returns the same error, so decryption cannot be used as a padding oracle
+    // (Manger's attack).
+    fn oaep_decode(mod_bytes: usize, encoded: &[u8], label: &[u8]) -> Result<Vec<u8>, Error> {
+        let h_len = <H as Digest>::output_size();
+
+        if mod_bytes < 2 * h_len + 2 || encoded.len() != mod_bytes {
+            return Err(Error::Decryption);
+        }
+
+        let (y, rest) = encoded.split_at(1);
+        let (masked_seed, masked_db) = rest.split_at(h_len);
+
+        let mut seed = masked_seed.to_vec();
+        let seed_mask = mgf1::<MGFH>(masked_db, h_len);
+        seed.iter_mut().zip(seed_mask).for_each(|(x, y)| *x ^= y);
+
+        let mut db = masked_db.to_vec();
+        let db_mask = mgf1::<MGFH>(&seed, db.len());
+        db.iter_mut().zip(db_mask).for_each(|(x, y)| *x ^= y);
+
+        let l_hash = H::digest(label);
+        let mut valid = y[0].ct_eq(&0) & db[..h_len].ct_eq(&l_hash);
+
+        // Looks for the 0x01 byte separating PS from M
+        let mut looking_for_one = Choice::from(1);
+        let mut message_start: u32 = 0;
+
+        for (i, x) in db[h_len..].iter().enumerate() {
+            let is_zero = x.ct_eq(&0);
+            let is_one = x.ct_eq(&1);
+
+            message_start.conditional_assign(&((h_len + i + 1) as u32), looking_for_one & is_one);
+
+            valid &= !looking_for_one | is_zero | is_one;
+            looking_for_one &= !is_one;
+        }
+
+        valid &= !looking_for_one;
+
+        if !bool::from(valid) {
+            return Err(Error::Decryption);
+        }
+
+        Ok(db[message_start as usize..].to_vec())
+    }
+
+    pub fn encrypt_with_label<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        plaintext: &[u8],
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let encoded = Self::oaep_encode(encoding::byte_len(&pk.n), plaintext, label, rng)?;
+
+        RSA::encrypt(pk, &encoded, rng)
+    }
+
+    pub fn decrypt_with_label<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        ciphertext: &[u8],
+        label: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+
+        if ciphertext.len() != mod_bytes {
+            return Err(Error::Decryption);
+        }
+
+        let encoded = RSA::decrypt_block(pk, sk, ciphertext, rng)?;
+
+        Self::oaep_decode(mod_bytes, &encoded, label)
+    }
+}
+
+impl<H: Digest, MGFH: Digest> PublicEnc for RSAOAEP<H, MGFH> {
+    type SecretKey = RSASecretKey;
+    type PublicKey = RSAPublicKey;
+
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+        RSA::keygen(sec_level, rng)
+    }
+
+    fn encrypt<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        plaintext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        Self::encrypt_with_label(pk, plaintext, &[], rng)
+    }
+
+    fn decrypt<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        ciphertext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        Self::decrypt_with_label(pk, sk, ciphertext, &[], rng)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rug::integer::Order;
+    use sha1::Sha1;
+    use sha2::{Sha256, Sha512};
+
+    use crate::{
+        error::Error, rsaoaep::algorithms::RSAOAEP, traits::public_enc::PublicEnc,
+        utils::rand::OsRandGen,
+    };
+
+    #[test]
+    fn rsa_oaep_encrypt_failes_for_message_out_of_range() {
+        let mut rng = OsRandGen::new();
+
+        let (_, pk) = RSAOAEP::<Sha256>::keygen(80, &mut rng).unwrap();
+
+        let m: Vec<u8> = pk.n.to_digits(Order::MsfBe);
+        assert_eq!(
+            RSAOAEP::<Sha256>::encrypt(&pk, &m, &mut rng),
+            Err(Error::MessageTooLong)
+        );
+
+        // k - 2hLen - 2 is the longest message that fits
+        let max_len = pk.n.significant_digits::<u8>() - 2 * 32 - 2;
+        assert!(RSAOAEP::<Sha256>::encrypt(&pk, &vec![1; max_len], &mut rng).is_ok());
+        assert_eq!(
+            RSAOAEP::<Sha256>::encrypt(&pk, &vec![1; max_len + 1], &mut rng),
+            Err(Error::MessageTooLong)
+        );
+    }
+
+    #[test]
+    fn rsa_oaep_encrypt_failes_for_hash_too_long_for_modulus() {
+        let mut rng = OsRandGen::new();
+
+        // 1024-bit modulus: 128 < 2 * 64 + 2
+        let (_, pk) = RSAOAEP::<Sha512>::keygen(80, &mut rng).unwrap();
+        assert_eq!(
+            RSAOAEP::<Sha512>::encrypt(&pk, b"", &mut rng),
+            Err(Error::ModulusTooShort)
+        );
+    }
+
+    #[test]
+    fn rsa_oaep_encrypt_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSAOAEP::<Sha256>::keygen(80, &mut rng).unwrap();
+        let input = ["", "test1", "test2", "test3"];
+
+        for s in input {
+            let m = s.as_bytes();
+
+            let c = RSAOAEP::<Sha256>::encrypt(&pk, m, &mut rng).unwrap();
+            let decrypted_message = RSAOAEP::<Sha256>::decrypt(&pk, &sk, &c, &mut rng).unwrap();
+            assert_eq!(m, &decrypted_message);
+
+            let c = RSAOAEP::<Sha1, Sha256>::encrypt(&pk, m, &mut rng).unwrap();
+            let decrypted_message =
+                RSAOAEP::<Sha1, Sha256>::decrypt(&pk, &sk, &c, &mut rng).unwrap();
+            assert_eq!(m, &decrypted_message);
+        }
+    }
+
+    #[test]
+    fn rsa_oaep_decrypt_checks_label_and_hash() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSAOAEP::<Sha256>::keygen(80, &mut rng).unwrap();
+        let m = b"test";
+
+        let c = RSAOAEP::<Sha256>::encrypt_with_label(&pk, m, b"label", &mut rng).unwrap();
+
+        let decrypted_message =
+            RSAOAEP::<Sha256>::decrypt_with_label(&pk, &sk, &c, b"label", &mut rng).unwrap();
+        assert_eq!(m, &decrypted_message[..]);
+
+        assert_eq!(
+            RSAOAEP::<Sha256>::decrypt_with_label(&pk, &sk, &c, b"other", &mut rng),
+            Err(Error::Decryption)
+        );
+        assert_eq!(
+            RSAOAEP::<Sha256>::decrypt(&pk, &sk, &c, &mut rng),
+            Err(Error::Decryption)
+        );
+        assert_eq!(
+            RSAOAEP::<Sha1>::decrypt_with_label(&pk, &sk, &c, b"label", &mut rng),
+            Err(Error::Decryption)
+        );
+    }
+}
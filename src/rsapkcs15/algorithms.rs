@@ -1,44 +1,56 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use subtle::{ConditionallySelectable, ConstantTimeEq, ConstantTimeGreater, ConstantTimeLess};
+
 use crate::{
+    error::Error,
     rsa::{
         algorithms::RSA,
         keys::{RSAPublicKey, RSASecretKey},
     },
     traits::public_enc::PublicEnc,
+    utils::{encoding, rand::SecureRng},
 };
 
+type HmacSha256 = Hmac<Sha256>;
+
+// Number of 16-bit candidates drawn for the length of the synthetic message
+const LENGTH_CANDIDATES: usize = 128;
+
 pub struct RSAPKCS15;
 
 impl RSAPKCS15 {
-    fn pkcs_encode(
+    // EM = 0x00 || 0x02 || PS || 0x00 || M, with |EM| = k (RFC 8017, Section 7.2.1)
+    fn pkcs_encode<R: SecureRng + ?Sized>(
         mod_bytes: usize,
         plaintext: &[u8],
-        rng: &mut rug::rand::RandState,
-    ) -> Result<Vec<u8>, String> {
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
         let plaintext_size = plaintext.len();
         let padded_plaintext_size = plaintext_size + 3 + 8;
 
         if padded_plaintext_size > mod_bytes {
-            return Err("The plaintext is too long.".to_string());
+            return Err(Error::MessageTooLong);
         }
 
-        let padding_bytes = mod_bytes - plaintext_size;
+        let padding_bytes = mod_bytes - plaintext_size - 3;
         let mut padded_plaintext: Vec<u8> = Vec::with_capacity(mod_bytes);
 
         padded_plaintext.push(0);
         padded_plaintext.push(2);
 
-        let mut x: u8;
+        let mut x = [0u8; 1];
 
-        for _ in 2..padding_bytes {
+        for _ in 0..padding_bytes {
             loop {
-                x = rng.bits(8) as u8;
+                rng.fill_bytes(&mut x);
 
-                if x != 0 {
+                if x[0] != 0 {
                     break;
                 }
             }
 
-            padded_plaintext.push(x);
+            padded_plaintext.push(x[0]);
         }
 
         padded_plaintext.push(0);
@@ -47,29 +59,102 @@ impl RSAPKCS15 {
         Ok(padded_plaintext)
     }
 
-    fn pkcs_decode(mod_bytes: usize, plaintext: &[u8]) -> Result<Vec<u8>, &'static str> {
-        let plaintext_size = plaintext.len();
+    // Decodes a k-byte EM in constant time. When the padding is invalid the
+    // synthetic message is returned instead, so that a Bleichenbacher-style
+    // attacker cannot tell a valid padding from an invalid one.
+    // synthetic holds k bytes, the message being its last synthetic_len ones.
+    fn pkcs_decode(encoded: &[u8], synthetic: &[u8], synthetic_len: u32) -> Vec<u8> {
+        let mod_bytes = encoded.len();
 
-        if plaintext_size < mod_bytes {
-            return Err("The plaintext size is less than mod_bytes.");
-        }
+        let mut good = encoded[0].ct_eq(&0) & encoded[1].ct_eq(&2);
+
+        // Index of the first zero byte after 0x00 || 0x02
+        let mut found_zero = subtle::Choice::from(0);
+        let mut zero_index: u32 = 0;
+
+        for (i, byte) in encoded.iter().enumerate().skip(2) {
+            let is_zero = byte.ct_eq(&0);
 
-        if plaintext[0] != 2 {
-            return Err("Malformed PKCS1.5 encoding: init bytes");
+            zero_index.conditional_assign(&(i as u32), !found_zero & is_zero);
+            found_zero |= is_zero;
         }
 
-        let padding_ends = plaintext[1..plaintext_size - 2]
+        // PS is at least 8 bytes long
+        good &= found_zero & zero_index.ct_gt(&9);
+
+        let message_len = mod_bytes as u32 - zero_index - 1;
+        let len = u32::conditional_select(&synthetic_len, &message_len, good);
+
+        // Both messages are right-aligned in k bytes
+        let selected: Vec<u8> = encoded
             .iter()
-            .position(|&x| x == 0)
-            .ok_or("Malformed PKCS1.5 encoding: padding_ends")?;
+            .zip(synthetic)
+            .map(|(e, s)| u8::conditional_select(s, e, good))
+            .collect();
+
+        selected[mod_bytes - len as usize..].to_vec()
+    }
+
+    // KDK = HMAC-SHA256(SHA256(I2OSP(d, k)), I2OSP(c, k))
+    // (draft-irtf-cfrg-rsa-guidance, Section 7.2)
+    fn derive_kdk(
+        sk: &RSASecretKey,
+        mod_bytes: usize,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let key = Sha256::digest(encoding::i2osp(&sk.d, mod_bytes)?);
 
-        if (padding_ends - 1) < 8 {
-            return Err("Malformed PKCS1.5 encoding: padding size is less than 8 bytes.");
+        let mut mac = HmacSha256::new_from_slice(&key).expect("HMAC accepts any key size");
+        mac.update(ciphertext);
+
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+
+    // PRF(KDK, label, L) = T_0 || T_1 || ..., truncated to len bytes, with
+    // T_i = HMAC-SHA256(KDK, I2OSP(i, 2) || label || I2OSP(L, 2)), L = 8 * len
+    fn prf(kdk: &[u8], label: &[u8], len: usize) -> Vec<u8> {
+        let bits = (len * 8) as u16;
+        let mut out: Vec<u8> = Vec::with_capacity(len + 32);
+        let mut i: u16 = 0;
+
+        while out.len() < len {
+            let mut mac = HmacSha256::new_from_slice(kdk).expect("HMAC accepts any key size");
+            mac.update(&i.to_be_bytes());
+            mac.update(label);
+            mac.update(&bits.to_be_bytes());
+            out.extend_from_slice(&mac.finalize().into_bytes());
+
+            i += 1;
         }
 
-        let out = plaintext[padding_ends + 2..].to_vec();
+        out.truncate(len);
+        out
+    }
+
+    // Returns the k pseudo-random bytes holding the synthetic message and its
+    // length, the last candidate length smaller than k - 10
+    fn synthetic_message(kdk: &[u8], mod_bytes: usize) -> (Vec<u8>, u32) {
+        let synthetic = Self::prf(kdk, b"message", mod_bytes);
+        let candidates = Self::prf(kdk, b"length", 2 * LENGTH_CANDIDATES);
+
+        let max_len = mod_bytes as u32 - 10;
 
-        Ok(out)
+        // Smallest mask covering max_len
+        let mut mask = max_len;
+        mask |= mask >> 1;
+        mask |= mask >> 2;
+        mask |= mask >> 4;
+        mask |= mask >> 8;
+
+        let mut len: u32 = 0;
+
+        for candidate in candidates.chunks_exact(2) {
+            let candidate = u16::from_be_bytes([candidate[0], candidate[1]]) as u32 & mask;
+
+            len.conditional_assign(&candidate, candidate.ct_lt(&max_len));
+        }
+
+        (synthetic, len)
     }
 }
 
@@ -77,68 +162,200 @@ impl PublicEnc for RSAPKCS15 {
     type SecretKey = RSASecretKey;
     type PublicKey = RSAPublicKey;
 
-    fn keygen(
+    fn keygen<R: SecureRng + ?Sized>(
         sec_level: u64,
-        rng: &mut rug::rand::RandState,
-    ) -> Result<(RSASecretKey, RSAPublicKey), String> {
+        rng: &mut R,
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
         RSA::keygen(sec_level, rng)
     }
 
-    fn encrypt(
+    fn encrypt<R: SecureRng + ?Sized>(
         pk: &RSAPublicKey,
         plaintext: &[u8],
-        rng: &mut rug::rand::RandState,
-    ) -> Result<Vec<u8>, String> {
-        let padded_plaintext =
-            RSAPKCS15::pkcs_encode(pk.n.significant_digits::<u8>(), plaintext, rng)?;
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let padded_plaintext = RSAPKCS15::pkcs_encode(encoding::byte_len(&pk.n), plaintext, rng)?;
 
         RSA::encrypt(pk, &padded_plaintext, rng)
     }
 
-    fn decrypt(pk: &RSAPublicKey, sk: &RSASecretKey, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
-        let padded_plaintext = RSA::decrypt(pk, sk, ciphertext)?;
-        let plaintext = RSAPKCS15::pkcs_decode(pk.n.significant_digits::<u8>(), &padded_plaintext)?;
+    // Implicit rejection: a ciphertext with an invalid padding decrypts to a
+    // message derived from the secret key and the ciphertext, instead of
+    // returning an error. Only malformed ciphertexts are rejected.
+    fn decrypt<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        ciphertext: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+
+        if ciphertext.len() != mod_bytes || mod_bytes < 11 {
+            return Err(Error::CiphertextOutOfRange);
+        }
+
+        let encoded = RSA::decrypt_block(pk, sk, ciphertext, rng)?;
+
+        let kdk = RSAPKCS15::derive_kdk(sk, mod_bytes, ciphertext)?;
+        let (synthetic, synthetic_len) = RSAPKCS15::synthetic_message(&kdk, mod_bytes);
 
-        Ok(plaintext)
+        Ok(RSAPKCS15::pkcs_decode(&encoded, &synthetic, synthetic_len))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use rug::{integer::Order, rand::RandState};
+    use rug::{integer::Order, Integer};
 
     use crate::{
-        rsapkcs15::algorithms::RSAPKCS15, traits::public_enc::PublicEnc,
-        utils::rand::rug_randseed_os_rng,
+        error::Error,
+        rsa::keys::RSASecretKey,
+        rsapkcs15::algorithms::RSAPKCS15,
+        traits::public_enc::PublicEnc,
+        utils::{encoding, rand::OsRandGen},
     };
 
+    const PKCS1_PEM: &str = include_str!("../../tests/fixtures/rsa2048-pkcs1.pem");
+    const CIPHERTEXT: &[u8] = include_bytes!("../../tests/fixtures/rsa2048-pkcs15.bin");
+    const INVALID_CIPHERTEXT: &[u8] =
+        include_bytes!("../../tests/fixtures/rsa2048-pkcs15-invalid.bin");
+    const SYNTHETIC_MESSAGE: &[u8] =
+        include_bytes!("../../tests/fixtures/rsa2048-pkcs15-invalid-synthetic.bin");
+
     #[test]
     fn rsa_pkcs15_encrypt_failes_for_message_out_of_range() {
-        let mut rng = RandState::new();
-
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let mut rng = OsRandGen::new();
 
         let (_, pk) = RSAPKCS15::keygen(80, &mut rng).unwrap();
 
         let m: Vec<u8> = pk.n.to_digits(Order::MsfBe);
-        assert!(RSAPKCS15::encrypt(&pk, &m, &mut rng).is_err());
+        assert_eq!(
+            RSAPKCS15::encrypt(&pk, &m, &mut rng),
+            Err(Error::MessageTooLong)
+        );
     }
 
     #[test]
     fn rsa_pkcs15_encrypt_works_as_expected() {
-        let mut rng = RandState::new();
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSAPKCS15::keygen(80, &mut rng).unwrap();
+        let max_len = pk.n.significant_digits::<u8>() - 11;
+        let input = [
+            "test1".as_bytes().to_vec(),
+            "test2".as_bytes().to_vec(),
+            vec![],
+            vec![0; 3],
+            vec![7; max_len],
+        ];
+
+        for m in input {
+            let c = RSAPKCS15::encrypt(&pk, &m, &mut rng).unwrap();
+
+            let decrypted_message = RSAPKCS15::decrypt(&pk, &sk, &c, &mut rng).unwrap();
+            assert_eq!(m, decrypted_message);
+        }
+    }
+
+    #[test]
+    fn rsa_pkcs15_decrypts_openssl_ciphertexts() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSASecretKey::from_pkcs1_pem(PKCS1_PEM).unwrap();
 
-        rug_randseed_os_rng(80, &mut rng).unwrap();
+        let m = RSAPKCS15::decrypt(&pk, &sk, CIPHERTEXT, &mut rng).unwrap();
+        assert_eq!(m, b"pke_rust fixture");
+
+        // OpenSSL implements the same implicit rejection
+        let m = RSAPKCS15::decrypt(&pk, &sk, INVALID_CIPHERTEXT, &mut rng).unwrap();
+        assert_eq!(m, SYNTHETIC_MESSAGE);
+    }
+
+    #[test]
+    fn rsa_pkcs15_invalid_ciphertexts_decrypt_to_synthetic_messages() {
+        let mut rng = OsRandGen::new();
 
         let (sk, pk) = RSAPKCS15::keygen(80, &mut rng).unwrap();
-        let input = ["test1", "test2", "test3"];
+        let mod_bytes = encoding::byte_len(&pk.n);
+        let c = encoding::i2osp(&Integer::from(12345), mod_bytes).unwrap();
+
+        // The synthetic message only depends on the key and the ciphertext
+        let m = RSAPKCS15::decrypt(&pk, &sk, &c, &mut rng).unwrap();
+        assert_eq!(RSAPKCS15::decrypt(&pk, &sk, &c, &mut rng).unwrap(), m);
+        assert!(m.len() <= mod_bytes - 11);
+
+        let other = encoding::i2osp(&Integer::from(12346), mod_bytes).unwrap();
+        assert_ne!(RSAPKCS15::decrypt(&pk, &sk, &other, &mut rng).unwrap(), m);
+
+        let (other_sk, other_pk) = RSAPKCS15::keygen(80, &mut rng).unwrap();
+        assert_ne!(
+            RSAPKCS15::decrypt(&other_pk, &other_sk, &c, &mut rng).unwrap(),
+            m
+        );
+    }
+
+    #[test]
+    fn rsa_pkcs15_decode_returns_the_synthetic_message_on_failure() {
+        let mod_bytes = 64;
+        let mut synthetic = vec![0xaa; mod_bytes];
+        synthetic[mod_bytes - 1] = 0xbb;
+        let expected = vec![0xaa, 0xbb];
+
+        let mut valid = vec![1; mod_bytes];
+        valid[0] = 0;
+        valid[1] = 2;
+        valid[10] = 0;
+        assert_eq!(
+            RSAPKCS15::pkcs_decode(&valid, &synthetic, 2),
+            vec![1; mod_bytes - 11]
+        );
+
+        // Wrong first byte
+        let mut block = valid.clone();
+        block[0] = 1;
+        assert_eq!(RSAPKCS15::pkcs_decode(&block, &synthetic, 2), expected);
+
+        // Wrong block type
+        let mut block = valid.clone();
+        block[1] = 1;
+        assert_eq!(RSAPKCS15::pkcs_decode(&block, &synthetic, 2), expected);
+
+        // Missing separator
+        let mut block = valid.clone();
+        block[10] = 1;
+        assert_eq!(RSAPKCS15::pkcs_decode(&block, &synthetic, 2), expected);
+
+        // Padding shorter than 8 bytes
+        let mut block = valid.clone();
+        block[5] = 0;
+        assert_eq!(RSAPKCS15::pkcs_decode(&block, &synthetic, 2), expected);
+    }
+
+    #[test]
+    fn rsa_pkcs15_decrypt_failes_for_ciphertext_out_of_range() {
+        let mut rng = OsRandGen::new();
 
-        for s in input {
-            let m = s.as_bytes();
-            let c = RSAPKCS15::encrypt(&pk, m, &mut rng).unwrap();
+        let (sk, pk) = RSAPKCS15::keygen(80, &mut rng).unwrap();
 
-            let decrypted_message = RSAPKCS15::decrypt(&pk, &sk, &c).unwrap();
-            assert_eq!(m, &decrypted_message);
-        }
+        let c: Vec<u8> = pk.n.to_digits(Order::MsfBe);
+        assert_eq!(
+            RSAPKCS15::decrypt(&pk, &sk, &c, &mut rng),
+            Err(Error::CiphertextOutOfRange)
+        );
+
+        let mut c = c;
+        c.insert(0, 1);
+        assert_eq!(
+            RSAPKCS15::decrypt(&pk, &sk, &c, &mut rng),
+            Err(Error::CiphertextOutOfRange)
+        );
+
+        // Ciphertexts are exactly as long as the modulus
+        let c = RSAPKCS15::encrypt(&pk, b"test", &mut rng).unwrap();
+        assert_eq!(c.len(), encoding::byte_len(&pk.n));
+        assert_eq!(
+            RSAPKCS15::decrypt(&pk, &sk, &c[1..], &mut rng),
+            Err(Error::CiphertextOutOfRange)
+        );
     }
 }
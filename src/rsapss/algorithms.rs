@@ -0,0 +1,275 @@
+use std::marker::PhantomData;
+
+use digest::Digest;
+use rug::{integer::IntegerExt64, Complete};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    error::Error,
+    rsa::{
+        algorithms::RSA,
+        keys::{RSAPublicKey, RSASecretKey},
+    },
+    traits::{public_enc::PublicEnc, signature::Signature},
+    utils::{
+        encoding,
+        mgf::mgf1,
+        rand::{self, SecureRng},
+    },
+};
+
+// RSASSA-PSS (RFC 8017, Section 8.1).
+// H is the hash function applied to the message, MGFH the one used by MGF1.
+// Signatures produced through the Signature trait use a salt as long as the hash.
+pub struct RSAPSS<H = Sha256, MGFH = H> {
+    _hash: PhantomData<(H, MGFH)>,
+}
+
+impl<H: Digest, MGFH: Digest> RSAPSS<H, MGFH> {
+    fn pss_encode<R: SecureRng + ?Sized>(
+        message: &[u8],
+        em_bits: u64,
+        salt_len: usize,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let h_len = <H as Digest>::output_size();
+        let em_len = em_bits.div_ceil(8) as usize;
+
+        if em_len < h_len + salt_len + 2 {
+            return Err(Error::ModulusTooShort);
+        }
+
+        let m_hash = H::digest(message);
+        let mut salt: Vec<u8> = vec![0; salt_len];
+        rng.fill_bytes(&mut salt);
+
+        // H = Hash(0x00 * 8 || mHash || salt)
+        let h = H::new()
+            .chain_update([0u8; 8])
+            .chain_update(&m_hash)
+            .chain_update(&salt)
+            .finalize();
+
+        // DB = PS || 0x01 || salt
+        let db_len = em_len - h_len - 1;
+        let mut db: Vec<u8> = vec![0; db_len - salt_len - 1];
+        db.push(1);
+        db.extend(salt);
+
+        let db_mask = mgf1::<MGFH>(&h, db_len);
+        db.iter_mut().zip(db_mask).for_each(|(x, y)| *x ^= y);
+        db[0] &= 0xff >> (8 * em_len as u64 - em_bits);
+
+        // EM = maskedDB || H || 0xbc
+        let mut encoded: Vec<u8> = Vec::with_capacity(em_len);
+        encoded.extend(db);
+        encoded.extend_from_slice(&h);
+        encoded.push(0xbc);
+
+        Ok(encoded)
+    }
+
+    fn pss_verify(
+        message: &[u8],
+        encoded: &[u8],
+        em_bits: u64,
+        salt_len: usize,
+    ) -> Result<(), Error> {
+        let h_len = <H as Digest>::output_size();
+        let em_len = em_bits.div_ceil(8) as usize;
+
+        if encoded.len() != em_len || em_len < h_len + salt_len + 2 {
+            return Err(Error::InvalidSignature);
+        }
+
+        if encoded[em_len - 1] != 0xbc {
+            return Err(Error::InvalidSignature);
+        }
+
+        let (masked_db, h) = encoded[..em_len - 1].split_at(em_len - h_len - 1);
+        let top_bits_mask = !(0xffu8 >> (8 * em_len as u64 - em_bits));
+
+        if masked_db[0] & top_bits_mask != 0 {
+            return Err(Error::InvalidSignature);
+        }
+
+        let mut db = masked_db.to_vec();
+        let db_mask = mgf1::<MGFH>(h, db.len());
+        db.iter_mut().zip(db_mask).for_each(|(x, y)| *x ^= y);
+        db[0] &= !top_bits_mask;
+
+        // DB must be PS || 0x01 || salt
+        let ps_len = db.len() - salt_len - 1;
+
+        if db[..ps_len].iter().any(|&x| x != 0) || db[ps_len] != 1 {
+            return Err(Error::InvalidSignature);
+        }
+
+        let salt = &db[ps_len + 1..];
+        let m_hash = H::digest(message);
+
+        let expected_h = H::new()
+            .chain_update([0u8; 8])
+            .chain_update(&m_hash)
+            .chain_update(salt)
+            .finalize();
+
+        if !bool::from(expected_h.ct_eq(h)) {
+            return Err(Error::InvalidSignature);
+        }
+
+        Ok(())
+    }
+
+    pub fn sign_with_salt_len<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        message: &[u8],
+        salt_len: usize,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+        let em_bits = pk.n.significant_bits_64() - 1;
+
+        let encoded = Self::pss_encode(message, em_bits, salt_len, rng)?;
+        let m = encoding::os2ip(&encoded);
+
+        let s = rand::with_rand_state(rng, |rng| RSA::crt_pow(pk, sk, &m, rng))?;
+
+        // The signature is always as long as the modulus
+        encoding::i2osp(&s, mod_bytes)
+    }
+
+    pub fn verify_with_salt_len(
+        pk: &RSAPublicKey,
+        message: &[u8],
+        signature: &[u8],
+        salt_len: usize,
+    ) -> Result<(), Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+        let em_bits = pk.n.significant_bits_64() - 1;
+        let em_len = em_bits.div_ceil(8) as usize;
+
+        if signature.len() != mod_bytes {
+            return Err(Error::InvalidSignature);
+        }
+
+        let s = encoding::os2ip(signature);
+
+        if s >= pk.n {
+            return Err(Error::InvalidSignature);
+        }
+
+        let m = match s.pow_mod_ref(&pk.e, &pk.n) {
+            Some(val) => val.complete(),
+            None => return Err(Error::InvalidSignature),
+        };
+
+        if m.significant_bits_64() > em_bits {
+            return Err(Error::InvalidSignature);
+        }
+
+        let encoded = encoding::i2osp(&m, em_len)?;
+
+        Self::pss_verify(message, &encoded, em_bits, salt_len)
+    }
+}
+
+impl<H: Digest, MGFH: Digest> Signature for RSAPSS<H, MGFH> {
+    type SecretKey = RSASecretKey;
+    type PublicKey = RSAPublicKey;
+
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+        RSA::keygen(sec_level, rng)
+    }
+
+    fn sign<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        Self::sign_with_salt_len(pk, sk, message, <H as Digest>::output_size(), rng)
+    }
+
+    fn verify(pk: &RSAPublicKey, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+        Self::verify_with_salt_len(pk, message, signature, <H as Digest>::output_size())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sha1::Sha1;
+    use sha2::{Sha256, Sha384};
+
+    use crate::{
+        error::Error, rsapss::algorithms::RSAPSS, traits::signature::Signature,
+        utils::rand::OsRandGen,
+    };
+
+    #[test]
+    fn rsa_pss_sign_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSAPSS::<Sha256>::keygen(80, &mut rng).unwrap();
+        let input = ["", "test1", "test2", "test3"];
+
+        for s in input {
+            let m = s.as_bytes();
+
+            let signature = RSAPSS::<Sha256>::sign(&pk, &sk, m, &mut rng).unwrap();
+            assert_eq!(signature.len(), pk.n.significant_digits::<u8>());
+            assert!(RSAPSS::<Sha256>::verify(&pk, m, &signature).is_ok());
+
+            let signature = RSAPSS::<Sha384, Sha1>::sign(&pk, &sk, m, &mut rng).unwrap();
+            assert!(RSAPSS::<Sha384, Sha1>::verify(&pk, m, &signature).is_ok());
+
+            let signature = RSAPSS::<Sha256>::sign_with_salt_len(&pk, &sk, m, 0, &mut rng).unwrap();
+            assert!(RSAPSS::<Sha256>::verify_with_salt_len(&pk, m, &signature, 0).is_ok());
+        }
+    }
+
+    #[test]
+    fn rsa_pss_verify_rejects_invalid_signatures() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSAPSS::<Sha256>::keygen(80, &mut rng).unwrap();
+        let (_, other_pk) = RSAPSS::<Sha256>::keygen(80, &mut rng).unwrap();
+
+        let m = b"test";
+        let signature = RSAPSS::<Sha256>::sign(&pk, &sk, m, &mut rng).unwrap();
+
+        assert_eq!(
+            RSAPSS::<Sha256>::verify(&pk, b"tesT", &signature),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            RSAPSS::<Sha256>::verify(&other_pk, m, &signature),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            RSAPSS::<Sha1>::verify(&pk, m, &signature),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            RSAPSS::<Sha256>::verify_with_salt_len(&pk, m, &signature, 0),
+            Err(Error::InvalidSignature)
+        );
+
+        let mut tampered = signature.clone();
+        tampered[10] ^= 1;
+        assert_eq!(
+            RSAPSS::<Sha256>::verify(&pk, m, &tampered),
+            Err(Error::InvalidSignature)
+        );
+
+        assert_eq!(
+            RSAPSS::<Sha256>::verify(&pk, m, &signature[1..]),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::marker::PhantomData;
+
+use digest::Digest;
+use rug::Complete;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512};
+use subtle::ConstantTimeEq;
+
+use crate::{
+    error::Error,
+    rsa::{
+        algorithms::RSA,
+        keys::{RSAPublicKey, RSASecretKey},
+    },
+    traits::{public_enc::PublicEnc, signature::Signature},
+    utils::{
+        encoding,
+        rand::{self, SecureRng},
+    },
+};
+
+// Hash functions that can be used with RSASSA-PKCS1-v1_5, together with the
+// DER encoding of their DigestInfo prefix (RFC 8017, Section 9.2, Note 1)
+pub trait DigestInfo: Digest {
+    const DIGEST_INFO_PREFIX: &'static [u8];
+}
+
+impl DigestInfo for Sha1 {
+    const DIGEST_INFO_PREFIX: &'static [u8] = &[
+        0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14,
+    ];
+}
+
+impl DigestInfo for Sha224 {
+    const DIGEST_INFO_PREFIX: &'static [u8] = &[
+        0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x04,
+        0x05, 0x00, 0x04, 0x1c,
+    ];
+}
+
+impl DigestInfo for Sha256 {
+    const DIGEST_INFO_PREFIX: &'static [u8] = &[
+        0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+        0x05, 0x00, 0x04, 0x20,
+    ];
+}
+
+impl DigestInfo for Sha384 {
+    const DIGEST_INFO_PREFIX: &'static [u8] = &[
+        0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02,
+        0x05, 0x00, 0x04, 0x30,
+    ];
+}
+
+impl DigestInfo for Sha512 {
+    const DIGEST_INFO_PREFIX: &'static [u8] = &[
+        0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03,
+        0x05, 0x00, 0x04, 0x40,
+    ];
+}
+
+// RSASSA-PKCS1-v1_5 (RFC 8017, Section 8.2)
+pub struct RSASSAPKCS15<H = Sha256> {
+    _hash: PhantomData<H>,
+}
+
+impl<H: DigestInfo> RSASSAPKCS15<H> {
+    fn pkcs_encode(mod_bytes: usize, message: &[u8]) -> Result<Vec<u8>, Error> {
+        let digest = H::digest(message);
+        let t_len = H::DIGEST_INFO_PREFIX.len() + digest.len();
+
+        if mod_bytes < t_len + 11 {
+            return Err(Error::ModulusTooShort);
+        }
+
+        // EM = 0x00 || 0x01 || PS || 0x00 || T
+        let mut encoded: Vec<u8> = Vec::with_capacity(mod_bytes);
+        encoded.push(0);
+        encoded.push(1);
+        encoded.resize(mod_bytes - t_len - 1, 0xff);
+        encoded.push(0);
+        encoded.extend_from_slice(H::DIGEST_INFO_PREFIX);
+        encoded.extend_from_slice(&digest);
+
+        Ok(encoded)
+    }
+}
+
+impl<H: DigestInfo> Signature for RSASSAPKCS15<H> {
+    type SecretKey = RSASecretKey;
+    type PublicKey = RSAPublicKey;
+
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(RSASecretKey, RSAPublicKey), Error> {
+        RSA::keygen(sec_level, rng)
+    }
+
+    fn sign<R: SecureRng + ?Sized>(
+        pk: &RSAPublicKey,
+        sk: &RSASecretKey,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+
+        let encoded = Self::pkcs_encode(mod_bytes, message)?;
+        let m = encoding::os2ip(&encoded);
+
+        let s = rand::with_rand_state(rng, |rng| RSA::crt_pow(pk, sk, &m, rng))?;
+
+        // The signature is always as long as the modulus
+        encoding::i2osp(&s, mod_bytes)
+    }
+
+    fn verify(pk: &RSAPublicKey, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+        let mod_bytes = encoding::byte_len(&pk.n);
+
+        if signature.len() != mod_bytes {
+            return Err(Error::InvalidSignature);
+        }
+
+        let s = encoding::os2ip(signature);
+
+        if s >= pk.n {
+            return Err(Error::InvalidSignature);
+        }
+
+        let m = match s.pow_mod_ref(&pk.e, &pk.n) {
+            Some(val) => val.complete(),
+            None => return Err(Error::InvalidSignature),
+        };
+
+        let encoded = encoding::i2osp(&m, mod_bytes)?;
+
+        // The encoding is deterministic, so the expected one is rebuilt and compared
+        let expected = Self::pkcs_encode(mod_bytes, message)?;
+
+        if !bool::from(encoded.ct_eq(&expected)) {
+            return Err(Error::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sha1::Sha1;
+    use sha2::{Sha256, Sha512};
+
+    use crate::{
+        error::Error, rsassapkcs15::algorithms::RSASSAPKCS15, traits::signature::Signature,
+        utils::rand::OsRandGen,
+    };
+
+    #[test]
+    fn rsa_ssa_pkcs15_sign_works_as_expected() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSASSAPKCS15::<Sha256>::keygen(80, &mut rng).unwrap();
+        let input = ["", "test1", "test2", "test3"];
+
+        for s in input {
+            let m = s.as_bytes();
+
+            let signature = RSASSAPKCS15::<Sha256>::sign(&pk, &sk, m, &mut rng).unwrap();
+            assert_eq!(signature.len(), pk.n.significant_digits::<u8>());
+            assert!(RSASSAPKCS15::<Sha256>::verify(&pk, m, &signature).is_ok());
+
+            // Signatures are deterministic
+            let other = RSASSAPKCS15::<Sha256>::sign(&pk, &sk, m, &mut rng).unwrap();
+            assert_eq!(signature, other);
+
+            let signature = RSASSAPKCS15::<Sha512>::sign(&pk, &sk, m, &mut rng).unwrap();
+            assert!(RSASSAPKCS15::<Sha512>::verify(&pk, m, &signature).is_ok());
+        }
+    }
+
+    #[test]
+    fn rsa_ssa_pkcs15_verify_rejects_invalid_signatures() {
+        let mut rng = OsRandGen::new();
+
+        let (sk, pk) = RSASSAPKCS15::<Sha256>::keygen(80, &mut rng).unwrap();
+        let (_, other_pk) = RSASSAPKCS15::<Sha256>::keygen(80, &mut rng).unwrap();
+
+        let m = b"test";
+        let signature = RSASSAPKCS15::<Sha256>::sign(&pk, &sk, m, &mut rng).unwrap();
+
+        assert_eq!(
+            RSASSAPKCS15::<Sha256>::verify(&pk, b"tesT", &signature),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            RSASSAPKCS15::<Sha256>::verify(&other_pk, m, &signature),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            RSASSAPKCS15::<Sha1>::verify(&pk, m, &signature),
+            Err(Error::InvalidSignature)
+        );
+
+        let mut tampered = signature.clone();
+        tampered[10] ^= 1;
+        assert_eq!(
+            RSASSAPKCS15::<Sha256>::verify(&pk, m, &tampered),
+            Err(Error::InvalidSignature)
+        );
+
+        assert_eq!(
+            RSASSAPKCS15::<Sha256>::verify(&pk, m, &signature[1..]),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
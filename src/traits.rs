@@ -1 +1,2 @@
 pub mod public_enc;
+pub mod signature;
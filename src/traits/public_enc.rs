@@ -1,26 +1,28 @@
-use rug::rand::RandState;
+use crate::{error::Error, utils::rand::SecureRng};
 
 pub trait PublicEnc {
     type SecretKey;
     type PublicKey;
 
     // keygen algorithm
-    fn keygen(
+    fn keygen<R: SecureRng + ?Sized>(
         sec_level: u64,
-        rng: &mut RandState,
-    ) -> Result<(Self::SecretKey, Self::PublicKey), String>;
+        rng: &mut R,
+    ) -> Result<(Self::SecretKey, Self::PublicKey), Error>;
 
     // encryption algorithm
-    fn encrypt(
+    fn encrypt<R: SecureRng + ?Sized>(
         pk: &Self::PublicKey,
         plaintext: &[u8],
-        rng: &mut RandState,
-    ) -> Result<Vec<u8>, String>;
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error>;
 
-    // decryption algorithm
-    fn decrypt(
+    // decryption algorithm,
+    // rng is used by the schemes that randomize decryption (e.g. RSA blinding)
+    fn decrypt<R: SecureRng + ?Sized>(
         pk: &Self::PublicKey,
         sk: &Self::SecretKey,
         ciphertext: &[u8],
-    ) -> Result<Vec<u8>, String>;
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error>;
 }
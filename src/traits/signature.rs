@@ -0,0 +1,23 @@
+use crate::{error::Error, utils::rand::SecureRng};
+
+pub trait Signature {
+    type SecretKey;
+    type PublicKey;
+
+    // keygen algorithm
+    fn keygen<R: SecureRng + ?Sized>(
+        sec_level: u64,
+        rng: &mut R,
+    ) -> Result<(Self::SecretKey, Self::PublicKey), Error>;
+
+    // signing algorithm
+    fn sign<R: SecureRng + ?Sized>(
+        pk: &Self::PublicKey,
+        sk: &Self::SecretKey,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error>;
+
+    // verification algorithm, fails if the signature is not valid
+    fn verify(pk: &Self::PublicKey, message: &[u8], signature: &[u8]) -> Result<(), Error>;
+}
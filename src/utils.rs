@@ -1 +1,5 @@
+pub mod encoding;
+pub mod mgf;
+pub mod primes;
 pub mod rand;
+pub mod zeroize;
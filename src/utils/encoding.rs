@@ -0,0 +1,70 @@
+use rug::{integer::Order, Integer};
+
+use crate::error::Error;
+
+// Length in bytes of the encoding of the elements modulo n
+pub fn byte_len(n: &Integer) -> usize {
+    n.significant_digits::<u8>()
+}
+
+// Integer-to-Octet-String primitive (RFC 8017, Section 4.1): x written
+// big-endian on exactly len bytes, left-padded with zeros
+pub fn i2osp(x: &Integer, len: usize) -> Result<Vec<u8>, Error> {
+    if x.is_negative() {
+        return Err(Error::Serialization);
+    }
+
+    let digits: Vec<u8> = x.to_digits(Order::MsfBe);
+
+    // integer too large
+    if digits.len() > len {
+        return Err(Error::Serialization);
+    }
+
+    let mut out: Vec<u8> = vec![0; len - digits.len()];
+    out.extend(digits);
+
+    Ok(out)
+}
+
+// Octet-String-to-Integer primitive (RFC 8017, Section 4.2)
+pub fn os2ip(bytes: &[u8]) -> Integer {
+    Integer::from_digits(bytes, Order::MsfBe)
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use super::*;
+
+    #[test]
+    fn i2osp_pads_to_the_requested_length() {
+        assert_eq!(i2osp(&Integer::from(0x0102), 4).unwrap(), [0, 0, 1, 2]);
+        assert_eq!(i2osp(&Integer::from(0x0102), 2).unwrap(), [1, 2]);
+        assert_eq!(i2osp(&Integer::ZERO, 3).unwrap(), [0, 0, 0]);
+        assert!(i2osp(&Integer::ZERO, 0).unwrap().is_empty());
+
+        assert_eq!(
+            i2osp(&Integer::from(0x0102), 1).unwrap_err(),
+            Error::Serialization
+        );
+        assert_eq!(
+            i2osp(&Integer::from(-1), 4).unwrap_err(),
+            Error::Serialization
+        );
+    }
+
+    #[test]
+    fn os2ip_inverts_i2osp() {
+        assert_eq!(os2ip(&[0, 0, 1, 2]), 0x0102);
+        assert_eq!(os2ip(&[]), 0);
+
+        let n = (Integer::from(1) << 1024u32) - 1u32;
+        let x = Integer::from(&n >> 700u32);
+        let encoded = i2osp(&x, byte_len(&n)).unwrap();
+
+        assert_eq!(encoded.len(), 128);
+        assert_eq!(os2ip(&encoded), x);
+    }
+}
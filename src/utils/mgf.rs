@@ -0,0 +1,60 @@
+use digest::Digest;
+
+// MGF1 mask generation function (RFC 8017, Appendix B.2.1)
+pub fn mgf1<D: Digest>(seed: &[u8], mask_len: usize) -> Vec<u8> {
+    let mut mask: Vec<u8> = Vec::with_capacity(mask_len + <D as Digest>::output_size());
+    let mut counter: u32 = 0;
+
+    while mask.len() < mask_len {
+        let mut hasher = D::new();
+        hasher.update(seed);
+        hasher.update(counter.to_be_bytes());
+        mask.extend_from_slice(&hasher.finalize());
+
+        counter += 1;
+    }
+
+    mask.truncate(mask_len);
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use sha1::Sha1;
+    use sha2::Sha256;
+
+    use super::*;
+
+    #[test]
+    fn mgf1_output_has_the_requested_length() {
+        for mask_len in [0, 1, 19, 20, 21, 64, 255] {
+            assert_eq!(mgf1::<Sha1>(b"seed", mask_len).len(), mask_len);
+            assert_eq!(mgf1::<Sha256>(b"seed", mask_len).len(), mask_len);
+        }
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn mgf1_matches_known_outputs() {
+        assert_eq!(to_hex(&mgf1::<Sha1>(b"foo", 3)), "1ac907");
+        assert_eq!(
+            to_hex(&mgf1::<Sha1>(b"bar", 50)),
+            "bc0c655e016bc2931d85a2e675181adcef7f581f76df2739da74faac41627be2f7f415c89e983fd0ce80ced9878641cb4876"
+        );
+        assert_eq!(
+            to_hex(&mgf1::<Sha256>(b"bar", 50)),
+            "382576a7841021cc28fc4c0948753fb8312090cea942ea4c4e735d10dc724b155f9f6069f289d61daca0cb814502ef04eae1"
+        );
+    }
+
+    #[test]
+    fn mgf1_longer_mask_extends_shorter_one() {
+        let short = mgf1::<Sha1>(b"foo", 10);
+        let long = mgf1::<Sha1>(b"foo", 50);
+
+        assert_eq!(short, long[..10]);
+    }
+}
@@ -0,0 +1,434 @@
+use std::{
+    panic,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    thread,
+};
+
+use rug::{
+    integer::{IntegerExt64, IsPrime, Order},
+    rand::RandState,
+    Complete, Integer,
+};
+use zeroize::Zeroize;
+
+use crate::{
+    error::Error,
+    utils::{rand::stream_rand_state, zeroize::zeroize_integer},
+};
+
+// Odd primes below this bound are used to sieve the candidates
+const SIEVE_BOUND: u32 = 2048;
+
+// Length of the incremental search before a fresh random start is drawn
+const MAX_DELTA: u64 = 1 << 16;
+
+// Smallest prime size accepted, so that the sieve never rejects a prime
+// because it is one of the small primes
+const MIN_PRIME_BITS: u64 = 16;
+
+fn small_primes() -> &'static [u32] {
+    static SMALL_PRIMES: OnceLock<Vec<u32>> = OnceLock::new();
+
+    SMALL_PRIMES.get_or_init(|| {
+        let mut is_composite = vec![false; SIEVE_BOUND as usize];
+        let mut primes = Vec::new();
+
+        for i in (3..SIEVE_BOUND as usize).step_by(2) {
+            if is_composite[i] {
+                continue;
+            }
+
+            primes.push(i as u32);
+
+            for j in (i * i..SIEVE_BOUND as usize).step_by(2 * i) {
+                is_composite[j] = true;
+            }
+        }
+
+        primes
+    })
+}
+
+// Miller-Rabin rounds for a prime of the given size, following
+// FIPS 186-5, Appendix B.3, Table B.1 (error probability below 2^-100
+// for random candidates). GMP runs a Baillie-PSW test before these rounds.
+pub fn mr_rounds(bits: u64) -> u32 {
+    match bits {
+        0..512 => 40,
+        512..1024 => 7,
+        1024..1536 => 5,
+        _ => 4,
+    }
+}
+
+// Random odd number of exactly bits bits with its two most significant bits
+// set, so that the product of two such numbers has exactly 2 * bits bits
+fn random_candidate(bits: u64, rng: &mut RandState) -> Integer {
+    let mut candidate = Integer::from(Integer::random_bits_64(bits, rng));
+
+    candidate.set_bit((bits - 1) as u32, true);
+    candidate.set_bit((bits - 2) as u32, true);
+    candidate.set_bit(0, true);
+
+    candidate
+}
+
+// Random prime of exactly bits bits with the top two bits set
+pub fn random_prime(bits: u64, rounds: u32, rng: &mut RandState) -> Result<Integer, Error> {
+    random_prime_with(bits, rounds, rng, |_| true)
+}
+
+// Random prime of exactly bits bits with the top two bits set, for which
+// accept returns true. Candidates are searched incrementally from a random
+// start: their residues modulo the small primes are computed once, so the
+// sieve costs a few word operations per candidate.
+pub fn random_prime_with(
+    bits: u64,
+    rounds: u32,
+    rng: &mut RandState,
+    mut accept: impl FnMut(&Integer) -> bool,
+) -> Result<Integer, Error> {
+    if bits < MIN_PRIME_BITS || rounds == 0 {
+        return Err(Error::InvalidParameters);
+    }
+
+    let small_primes = small_primes();
+
+    loop {
+        let start = random_candidate(bits, rng);
+        let residues: Vec<u64> = small_primes
+            .iter()
+            .map(|&s| start.mod_u(s) as u64)
+            .collect();
+
+        for delta in (0..MAX_DELTA).step_by(2) {
+            let survives = residues
+                .iter()
+                .zip(small_primes)
+                .all(|(r, &s)| !(r + delta).is_multiple_of(s as u64));
+
+            if !survives {
+                continue;
+            }
+
+            let candidate = (&start + delta).complete();
+
+            if candidate.significant_bits_64() != bits {
+                break;
+            }
+
+            if candidate.is_probably_prime(rounds) != IsPrime::No && accept(&candidate) {
+                return Ok(candidate);
+            }
+        }
+    }
+}
+
+// Random safe prime p = 2q + 1 of exactly bits bits, returned with q.
+// Both p and q are sieved, then a single round on q and a base-2 Fermat
+// test on p discard most candidates before the full tests.
+pub fn safe_prime(
+    bits: u64,
+    rounds: u32,
+    rng: &mut RandState,
+) -> Result<(Integer, Integer), Error> {
+    if bits < MIN_PRIME_BITS + 1 || rounds == 0 {
+        return Err(Error::InvalidParameters);
+    }
+
+    loop {
+        let start = random_candidate(bits - 1, rng);
+
+        if let Some(found) = safe_prime_from(&start, bits, rounds, || false) {
+            return Ok(found);
+        }
+    }
+}
+
+// Same as safe_prime, with the search spread over the given number of threads.
+// The random starts are numbered: worker i takes starts i, i + threads, ...
+// drawn from its own stream, keyed from rng. The safe prime found from the
+// lowest start is returned, so the result only depends on rng and threads.
+pub fn safe_prime_parallel(
+    bits: u64,
+    rounds: u32,
+    threads: usize,
+    rng: &mut RandState,
+) -> Result<(Integer, Integer), Error> {
+    if bits < MIN_PRIME_BITS + 1 || rounds == 0 || threads == 0 {
+        return Err(Error::InvalidParameters);
+    }
+
+    let keys: Vec<[u8; 32]> = (0..threads).map(|_| worker_key(rng)).collect();
+
+    // Lowest start from which a safe prime was found
+    let best = AtomicU64::new(u64::MAX);
+    let best = &best;
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = keys
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut key)| {
+                scope.spawn(move || {
+                    let mut rng = stream_rand_state(key);
+                    key.zeroize();
+
+                    let starts = (i as u64..).step_by(threads);
+
+                    for index in starts {
+                        // A lower start already succeeded
+                        if index > best.load(Ordering::Relaxed) {
+                            break;
+                        }
+
+                        let start = random_candidate(bits - 1, &mut rng);
+                        let stop = || best.load(Ordering::Relaxed) < index;
+
+                        if let Some(found) = safe_prime_from(&start, bits, rounds, stop) {
+                            best.fetch_min(index, Ordering::Relaxed);
+                            return Some((index, found));
+                        }
+                    }
+
+                    None
+                })
+            })
+            .collect();
+
+        let mut result: Option<(u64, (Integer, Integer))> = None;
+
+        for worker in workers {
+            match worker.join() {
+                Ok(Some((index, found))) if result.as_ref().is_none_or(|(i, _)| index < *i) => {
+                    result = Some((index, found))
+                }
+                Ok(_) => {}
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+
+        result.map(|(_, found)| found).ok_or(Error::KeyGeneration)
+    })
+}
+
+// Key of a worker's stream, drawn from rng
+fn worker_key(rng: &mut RandState) -> [u8; 32] {
+    let mut key = [0u8; 32];
+
+    let mut x = Integer::from(Integer::random_bits(256, rng));
+    x.write_digits(&mut key, Order::MsfBe);
+    zeroize_integer(&mut x);
+
+    key
+}
+
+// Looks for a safe prime p = 2q + 1 of bits bits with q in
+// [start, start + MAX_DELTA), until stop returns true
+fn safe_prime_from(
+    start: &Integer,
+    bits: u64,
+    rounds: u32,
+    stop: impl Fn() -> bool,
+) -> Option<(Integer, Integer)> {
+    let small_primes = small_primes();
+    let two = Integer::from(2);
+
+    let residues: Vec<u64> = small_primes
+        .iter()
+        .map(|&s| start.mod_u(s) as u64)
+        .collect();
+
+    for delta in (0..MAX_DELTA).step_by(2) {
+        // s divides neither q nor 2q + 1
+        let survives = residues.iter().zip(small_primes).all(|(r, &s)| {
+            let s = s as u64;
+            let r = (r + delta) % s;
+
+            r != 0 && !(2 * r + 1).is_multiple_of(s)
+        });
+
+        if !survives {
+            continue;
+        }
+
+        if stop() {
+            return None;
+        }
+
+        let q = (start + delta).complete();
+
+        if q.significant_bits_64() != bits - 1 {
+            return None;
+        }
+
+        if q.is_probably_prime(1) == IsPrime::No {
+            continue;
+        }
+
+        let p: Integer = (&q << 1u32).complete() + 1u32;
+        let p_minus_one = (&p - Integer::ONE).complete();
+        let fermat = two.pow_mod_ref(&p_minus_one, &p).map(Integer::from);
+
+        if fermat.is_none_or(|r| r != 1) {
+            continue;
+        }
+
+        if q.is_probably_prime(rounds) != IsPrime::No && p.is_probably_prime(rounds) != IsPrime::No
+        {
+            return Some((p, q));
+        }
+    }
+
+    None
+}
+
+// Probable primality test with the FIPS 186-5 number of rounds for n
+pub fn is_prime(n: &Integer) -> bool {
+    n.is_probably_prime(mr_rounds(n.significant_bits_64())) != IsPrime::No
+}
+
+// Checks whether n is divisible by one of the small primes other than itself
+// (SP 800-89, Section 5.3.3 requires no factor below 752)
+pub fn has_small_factor(n: &Integer) -> bool {
+    small_primes()
+        .iter()
+        .any(|&s| n.is_divisible_u(s) && *n != s)
+}
+
+// |r_i - r_j| > 2^(b - 100) for every pair of primes, b being the size
+// of the smallest one (FIPS 186-5, A.1.3)
+pub fn are_far_apart(primes: &[Integer]) -> bool {
+    let min_bits = primes
+        .iter()
+        .map(|r| r.significant_bits())
+        .min()
+        .unwrap_or(0);
+    let bound = Integer::from(1) << min_bits.saturating_sub(100);
+
+    primes.iter().enumerate().all(|(i, r_i)| {
+        primes[i + 1..]
+            .iter()
+            .all(|r_j| (r_i - r_j).complete().abs() > bound)
+    })
+}
+
+// d > 2^(nlen / 2), which rules out small private exponent attacks
+// (FIPS 186-5, A.1.1)
+pub fn d_is_large_enough(d: &Integer, mod_bits: u64) -> bool {
+    *d > (Integer::from(1) << (mod_bits / 2) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::{integer::IsPrime, rand::RandState, Integer};
+
+    use crate::{
+        error::Error,
+        utils::rand::{deterministic_rand_state, rug_randseed_os_rng, secure_rand_state},
+    };
+
+    use super::*;
+
+    #[test]
+    fn small_primes_are_the_odd_primes_below_the_bound() {
+        let primes = small_primes();
+
+        assert_eq!(&primes[..5], &[3, 5, 7, 11, 13]);
+        assert_eq!(primes.len(), 308);
+        assert!(primes
+            .iter()
+            .all(|&s| Integer::from(s).is_probably_prime(30) == IsPrime::Yes));
+    }
+
+    #[test]
+    fn random_primes_have_the_top_two_bits_set() {
+        let mut rng = RandState::new();
+
+        rug_randseed_os_rng(80, &mut rng).unwrap();
+
+        for bits in [16, 17, 256, 512] {
+            let p = random_prime(bits, mr_rounds(bits), &mut rng).unwrap();
+
+            assert_eq!(p.significant_bits() as u64, bits);
+            assert!(p.get_bit(bits as u32 - 2));
+            assert_ne!(p.is_probably_prime(30), IsPrime::No);
+        }
+
+        let p = random_prime_with(64, 40, &mut rng, |p| p.mod_u(4) == 3).unwrap();
+        assert_eq!(p.mod_u(4), 3);
+
+        assert_eq!(
+            random_prime(8, 40, &mut rng).unwrap_err(),
+            Error::InvalidParameters
+        );
+        assert_eq!(
+            random_prime(512, 0, &mut rng).unwrap_err(),
+            Error::InvalidParameters
+        );
+    }
+
+    #[test]
+    fn safe_primes_work_as_expected() {
+        let mut rng = RandState::new();
+
+        rug_randseed_os_rng(80, &mut rng).unwrap();
+
+        let (p, q) = safe_prime(256, mr_rounds(256), &mut rng).unwrap();
+
+        assert_eq!(p.significant_bits(), 256);
+        assert_eq!(p, Integer::from(&q << 1u32) + 1u32);
+        assert_ne!(p.is_probably_prime(30), IsPrime::No);
+        assert_ne!(q.is_probably_prime(30), IsPrime::No);
+    }
+
+    #[test]
+    fn parallel_safe_primes_work_as_expected() {
+        let mut rng = secure_rand_state();
+
+        let (p, q) = safe_prime_parallel(256, mr_rounds(256), 4, &mut rng).unwrap();
+
+        assert_eq!(p.significant_bits(), 256);
+        assert_eq!(p, Integer::from(&q << 1u32) + 1u32);
+        assert_ne!(p.is_probably_prime(30), IsPrime::No);
+        assert_ne!(q.is_probably_prime(30), IsPrime::No);
+
+        assert_eq!(
+            safe_prime_parallel(256, mr_rounds(256), 0, &mut rng).unwrap_err(),
+            Error::InvalidParameters
+        );
+    }
+
+    #[test]
+    fn parallel_safe_primes_are_reproducible() {
+        for threads in [1, 3, 8] {
+            let mut a = deterministic_rand_state([5; 32]);
+            let mut b = deterministic_rand_state([5; 32]);
+
+            let (p_a, _) = safe_prime_parallel(256, mr_rounds(256), threads, &mut a).unwrap();
+            let (p_b, _) = safe_prime_parallel(256, mr_rounds(256), threads, &mut b).unwrap();
+
+            assert_eq!(p_a, p_b);
+        }
+    }
+
+    #[test]
+    fn small_factors_are_detected() {
+        assert!(has_small_factor(&Integer::from(2039 * 65537)));
+        assert!(has_small_factor(&Integer::from(3 * 5)));
+        assert!(!has_small_factor(&Integer::from(2039)));
+        assert!(!has_small_factor(&Integer::from(65537u64 * 65539)));
+
+        assert!(is_prime(&Integer::from(65537)));
+        assert!(!is_prime(&Integer::from(65537u64 * 65539)));
+    }
+
+    #[test]
+    fn private_exponent_size_is_checked() {
+        assert!(d_is_large_enough(&(Integer::from(1) << 1025u32), 2048));
+        assert!(!d_is_large_enough(&(Integer::from(1) << 1024u32), 2048));
+    }
+}
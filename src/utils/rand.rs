@@ -1,12 +1,22 @@
-use rand_core::{OsRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{impls, CryptoRng, CryptoRngCore, OsRng, RngCore, SeedableRng};
 use rug::integer::Order::MsfBe;
-use rug::{rand::RandState, Integer};
+use rug::{
+    rand::{RandGen, RandState},
+    Integer,
+};
+use zeroize::Zeroize;
 
-pub fn get_randseed_os_rng(seed_bits: usize) -> Result<Integer, String> {
+use crate::error::Error;
+
+// Number of 32-bit words OsRandGen outputs before drawing a new key from the OS
+const RESEED_INTERVAL: u64 = 1 << 16;
+
+pub fn get_randseed_os_rng(seed_bits: usize) -> Result<Integer, Error> {
     let seed_bytes: usize = seed_bits / 8;
 
     if seed_bytes < 1 {
-        return Err("seed_bytes < 1".to_string());
+        return Err(Error::InvalidSeedSize);
     }
 
     let mut seed: Vec<u8> = vec![0; seed_bytes];
@@ -16,22 +26,154 @@ pub fn get_randseed_os_rng(seed_bits: usize) -> Result<Integer, String> {
     Ok(seed)
 }
 
-pub fn rug_randseed_os_rng(seed_bits: usize, rng: &mut RandState) -> Result<(), String> {
+pub fn rug_randseed_os_rng(seed_bits: usize, rng: &mut RandState) -> Result<(), Error> {
     let seed = get_randseed_os_rng(seed_bits)?;
     rng.seed(&seed);
 
     Ok(())
 }
 
+// Random sources accepted by the schemes: any rand_core CSPRNG
+pub trait SecureRng: CryptoRngCore {}
+
+impl<R: CryptoRngCore + ?Sized> SecureRng for R {}
+
+// rug generator replaying a ChaCha20 stream
+struct StreamGen(ChaCha20Rng);
+
+impl RandGen for StreamGen {
+    fn gen(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+}
+
+// RandState replaying the ChaCha20 stream keyed with key, which must be
+// secret: it is used to hand independent streams derived from one generator
+// to several threads
+pub(crate) fn stream_rand_state(key: [u8; 32]) -> RandState<'static> {
+    RandState::new_custom_boxed(Box::new(StreamGen(ChaCha20Rng::from_seed(key))))
+}
+
+// Runs f with a RandState for rug's random functions, drawing from rng.
+// rug requires its generators to be Send and Sync, which a rand_core CSPRNG
+// need not be (e.g. rand's ThreadRng), so the RandState replays a ChaCha20
+// stream keyed with 32 bytes of rng rather than borrowing rng itself.
+pub fn with_rand_state<R, T>(rng: &mut R, f: impl FnOnce(&mut RandState) -> T) -> T
+where
+    R: SecureRng + ?Sized,
+{
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+
+    let mut gen = StreamGen(ChaCha20Rng::from_seed(key));
+    key.zeroize();
+
+    let mut state = RandState::new_custom(&mut gen);
+
+    f(&mut state)
+}
+
+// Cryptographically secure generator: a ChaCha20 stream whose key is drawn
+// from the OS CSPRNG and replaced every RESEED_INTERVAL words. Unlike the
+// default Mersenne Twister of RandState, its output cannot be predicted from
+// previous outputs, so padding bytes, Paillier's r and ElGamal's k stay secret.
+pub struct OsRandGen {
+    stream: ChaCha20Rng,
+    words: u64,
+}
+
+impl OsRandGen {
+    pub fn new() -> Self {
+        Self {
+            stream: Self::os_stream(),
+            words: 0,
+        }
+    }
+
+    fn os_stream() -> ChaCha20Rng {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+
+        let stream = ChaCha20Rng::from_seed(key);
+        key.zeroize();
+
+        stream
+    }
+}
+
+impl Default for OsRandGen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RngCore for OsRandGen {
+    fn next_u32(&mut self) -> u32 {
+        if self.words == RESEED_INTERVAL {
+            self.stream = Self::os_stream();
+            self.words = 0;
+        }
+
+        self.words += 1;
+        self.stream.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+
+        Ok(())
+    }
+}
+
+impl CryptoRng for OsRandGen {}
+
+impl RandGen for OsRandGen {
+    fn gen(&mut self) -> u32 {
+        self.next_u32()
+    }
+
+    // The generator is always keyed by the OS: a caller-supplied seed, as in
+    // rug_randseed_os_rng, is ignored
+    fn seed(&mut self, _seed: &Integer) {}
+
+    // A clone must not replay the stream of the original, it gets its own key
+    fn boxed_clone(&self) -> Option<Box<dyn RandGen>> {
+        Some(Box::new(Self::new()))
+    }
+}
+
+// RandState backed by OsRandGen, for the functions working on rug's
+// generators directly (e.g. utils::primes)
+pub fn secure_rand_state() -> RandState<'static> {
+    RandState::new_custom_boxed(Box::new(OsRandGen::new()))
+}
+
+#[cfg(any(test, feature = "test-rng"))]
+mod deterministic;
+#[cfg(any(test, feature = "test-rng"))]
+pub use deterministic::{deterministic_rand_state, DeterministicRandGen};
+
 #[cfg(test)]
 mod tests {
+    use std::{marker::PhantomData, rc::Rc};
+
+    use crate::{rsa::algorithms::RSA, traits::public_enc::PublicEnc};
+
     use super::*;
 
     #[test]
     fn get_randseed_os_rng_seed_bits_error() {
         for i in 0..8 {
             let r = get_randseed_os_rng(i);
-            assert!(r.is_err());
+            assert_eq!(r, Err(Error::InvalidSeedSize));
         }
     }
 
@@ -52,4 +194,123 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn secure_rand_state_is_not_reset_by_seeding() {
+        let mut a = secure_rand_state();
+        let mut b = secure_rand_state();
+
+        a.seed(&Integer::from(42));
+        b.seed(&Integer::from(42));
+
+        let x = Integer::from(Integer::random_bits(1024, &mut a));
+        let y = Integer::from(Integer::random_bits(1024, &mut b));
+        assert_ne!(x, y);
+
+        // A clone has its own key
+        let mut c = a.clone();
+        let x = Integer::from(Integer::random_bits(1024, &mut a));
+        let z = Integer::from(Integer::random_bits(1024, &mut c));
+        assert_ne!(x, z);
+    }
+
+    #[test]
+    fn os_rand_gen_reseeds_after_the_interval() {
+        let mut generator = OsRandGen::new();
+
+        for _ in 0..RESEED_INTERVAL {
+            generator.gen();
+        }
+        assert_eq!(generator.words, RESEED_INTERVAL);
+
+        generator.gen();
+        assert_eq!(generator.words, 1);
+    }
+
+    #[test]
+    fn deterministic_rand_state_replays_the_same_stream() {
+        let mut a = deterministic_rand_state([7; 32]);
+        let mut b = deterministic_rand_state([7; 32]);
+        let mut c = deterministic_rand_state([8; 32]);
+
+        let x = Integer::from(Integer::random_bits(1024, &mut a));
+        let y = Integer::from(Integer::random_bits(1024, &mut b));
+        let z = Integer::from(Integer::random_bits(1024, &mut c));
+
+        assert_eq!(x, y);
+        assert_ne!(x, z);
+
+        // Reseeding through RandState restarts the stream
+        a.seed(&Integer::from(42));
+        b.seed(&Integer::from(42));
+        assert_eq!(a.bits(32), b.bits(32));
+
+        // Clones continue from the same position
+        let mut d = a.clone();
+        assert_eq!(a.bits(32), d.bits(32));
+    }
+
+    #[test]
+    fn deterministic_rand_gen_replays_keygen() {
+        let mut a = DeterministicRandGen::from_seed([1; 32]);
+        let mut b = DeterministicRandGen::from_seed([1; 32]);
+
+        let (_, pk_a) = RSA::keygen(80, &mut a).unwrap();
+        let (_, pk_b) = RSA::keygen(80, &mut b).unwrap();
+
+        assert_eq!(pk_a.n, pk_b.n);
+        assert_eq!(pk_a.e, pk_b.e);
+        assert_eq!(
+            RSA::encrypt(&pk_a, b"t1", &mut a).unwrap(),
+            RSA::encrypt(&pk_b, b"t1", &mut b).unwrap()
+        );
+    }
+
+    #[test]
+    fn with_rand_state_draws_from_the_rand_core_rng() {
+        let mut a = DeterministicRandGen::from_seed([3; 32]);
+        let mut b = DeterministicRandGen::from_seed([3; 32]);
+
+        // The same rng state gives the same RandState stream
+        let x = with_rand_state(&mut a, |rng| Integer::from(Integer::random_bits(1024, rng)));
+        let y = with_rand_state(&mut b, |rng| Integer::from(Integer::random_bits(1024, rng)));
+        assert_eq!(x, y);
+
+        // And each call consumes the 32-byte key from the rand_core rng
+        let mut c = DeterministicRandGen::from_seed([3; 32]);
+        c.fill_bytes(&mut [0u8; 32]);
+        assert_eq!(a.next_u64(), c.next_u64());
+    }
+
+    // A CSPRNG that is neither Send nor Sync, like rand's ThreadRng
+    struct LocalRng(OsRng, PhantomData<Rc<()>>);
+
+    impl RngCore for LocalRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.0.fill_bytes(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.0.try_fill_bytes(dest)
+        }
+    }
+
+    impl CryptoRng for LocalRng {}
+
+    #[test]
+    fn schemes_accept_rngs_that_are_not_send() {
+        let mut rng = LocalRng(OsRng, PhantomData);
+
+        let (sk, pk) = RSA::keygen(80, &mut rng).unwrap();
+        let c = RSA::encrypt(&pk, b"t1", &mut rng).unwrap();
+        assert_eq!(RSA::decrypt(&pk, &sk, &c, &mut rng).unwrap(), b"t1");
+    }
 }
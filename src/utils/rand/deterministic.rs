@@ -0,0 +1,66 @@
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+use rug::integer::Order::MsfBe;
+use rug::{
+    rand::{RandGen, RandState},
+    Integer,
+};
+use sha2::{Digest, Sha256};
+
+// Deterministic generator, for reproducible tests and regression vectors: the
+// output is the ChaCha20 stream keyed with a 32-byte seed, so the same seed
+// always replays the same keys and ciphertexts.
+// It must never be used for production keys, which are generated with
+// OsRandGen: this module is only compiled for the crate's own tests and with
+// the test-rng feature.
+#[derive(Clone)]
+pub struct DeterministicRandGen(ChaCha20Rng);
+
+impl DeterministicRandGen {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self(ChaCha20Rng::from_seed(seed))
+    }
+}
+
+impl RngCore for DeterministicRandGen {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+// The seed is known, so the output is not secret: the marker is only there to
+// pass the generator to the schemes in tests, see the warning above
+impl CryptoRng for DeterministicRandGen {}
+
+impl RandGen for DeterministicRandGen {
+    fn gen(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    // RandState::seed rekeys the generator with SHA-256(seed)
+    fn seed(&mut self, seed: &Integer) {
+        let key = Sha256::digest(seed.to_digits::<u8>(MsfBe));
+        self.0 = ChaCha20Rng::from_seed(key.into());
+    }
+
+    fn boxed_clone(&self) -> Option<Box<dyn RandGen>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+// RandState backed by DeterministicRandGen, see the warning above
+pub fn deterministic_rand_state(seed: [u8; 32]) -> RandState<'static> {
+    RandState::new_custom_boxed(Box::new(DeterministicRandGen::from_seed(seed)))
+}
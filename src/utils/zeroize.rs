@@ -0,0 +1,44 @@
+use rug::Integer;
+use zeroize::Zeroize;
+
+// Overwrites every allocated limb of x with zeros and sets x to 0.
+// Only the final value is wiped: copies made by GMP while computing it
+// (temporaries, reallocations) are not reachable from here.
+pub fn zeroize_integer(x: &mut Integer) {
+    // Safety: d points to alloc limbs owned by x, and size is reset to 0
+    // so that x is a valid representation of 0 afterwards.
+    unsafe {
+        let raw = x.as_raw_mut();
+        let limbs = std::slice::from_raw_parts_mut((*raw).d.as_ptr(), (*raw).alloc as usize);
+
+        limbs.zeroize();
+        (*raw).size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rug::Integer;
+
+    use super::*;
+
+    #[test]
+    fn zeroize_integer_wipes_all_limbs() {
+        let mut x = Integer::from(u128::MAX) * Integer::from(u128::MAX);
+        zeroize_integer(&mut x);
+
+        assert_eq!(x, 0);
+
+        let raw = x.as_raw();
+        let limbs = unsafe { std::slice::from_raw_parts((*raw).d.as_ptr(), (*raw).alloc as usize) };
+        assert!(limbs.iter().all(|&l| l == 0));
+    }
+
+    #[test]
+    fn zeroize_integer_works_on_zero() {
+        let mut x = Integer::new();
+        zeroize_integer(&mut x);
+
+        assert_eq!(x, 0);
+    }
+}
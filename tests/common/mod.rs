@@ -0,0 +1,100 @@
+// Shared by the integration tests: parser for the vector files and key
+// loading. Each test crate uses only part of it.
+#![allow(dead_code)]
+
+use std::{collections::HashMap, fs};
+
+use pke_rust::rsa::keys::{RSAPublicKey, RSASecretKey};
+use rug::Integer;
+
+// A block of "name = value" lines, values being hex strings
+pub struct Block(HashMap<String, String>);
+
+impl Block {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    pub fn bytes(&self, name: &str) -> Vec<u8> {
+        let value = self.get(name).unwrap_or_else(|| panic!("missing {name}"));
+
+        hex(value)
+    }
+
+    pub fn int(&self, name: &str) -> Integer {
+        let value = self.get(name).unwrap_or_else(|| panic!("missing {name}"));
+
+        Integer::from_str_radix(value, 16).unwrap()
+    }
+}
+
+// Decodes a hex string
+pub fn hex(value: &str) -> Vec<u8> {
+    assert!(value.len().is_multiple_of(2), "odd length for {value}");
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// Reads a file given relative to tests/
+pub fn read(file: &str) -> String {
+    let path = format!("{}/tests/{file}", env!("CARGO_MANIFEST_DIR"));
+
+    fs::read_to_string(&path).unwrap_or_else(|_| panic!("cannot read {path}"))
+}
+
+// Parses a vector file, given relative to tests/: blocks are separated by
+// blank lines and lines starting with '#' are comments. The first block
+// holds the key.
+pub fn load(file: &str) -> (Block, Vec<Block>) {
+    let text = read(file);
+
+    let mut blocks = Vec::new();
+    let mut current = HashMap::new();
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                blocks.push(Block(std::mem::take(&mut current)));
+            }
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once('=')
+            .unwrap_or_else(|| panic!("malformed line in {file}: {line}"));
+        current.insert(name.trim().to_string(), value.trim().to_string());
+    }
+
+    if !current.is_empty() {
+        blocks.push(Block(current));
+    }
+
+    let mut blocks = blocks.into_iter();
+    let key = blocks.next().expect("missing key block");
+
+    (key, blocks.collect())
+}
+
+pub fn rsa_key(key: &Block) -> (RSASecretKey, RSAPublicKey) {
+    let pk = RSAPublicKey::new(key.int("n"), key.int("e"));
+    let sk = RSASecretKey::new(
+        key.int("p"),
+        key.int("q"),
+        key.int("d"),
+        key.int("d_p"),
+        key.int("d_q"),
+        key.int("q_inv"),
+    );
+
+    pk.validate().unwrap();
+    sk.validate_pair(&pk).unwrap();
+
+    (sk, pk)
+}
@@ -0,0 +1,49 @@
+// Interoperability tests: the vectors in tests/interop were produced with
+// OpenSSL and checked with Python, see the header of each file. They are
+// not published vectors, those live in tests/vectors.
+
+mod common;
+
+use pke_rust::{
+    rsa::algorithms::RSA, rsapkcs15::algorithms::RSAPKCS15, traits::public_enc::PublicEnc,
+    utils::rand::OsRandGen,
+};
+
+use common::{load, rsa_key};
+
+#[test]
+fn rsa_matches_openssl() {
+    let (key, vectors) = load("interop/rsa.txt");
+    let (sk, pk) = rsa_key(&key);
+    let mut rng = OsRandGen::new();
+
+    assert!(!vectors.is_empty());
+
+    for v in vectors {
+        let msg = v.bytes("msg");
+        let ct = v.bytes("ct");
+
+        assert_eq!(RSA::encrypt(&pk, &msg, &mut rng).unwrap(), ct);
+        assert_eq!(RSA::decrypt(&pk, &sk, &ct, &mut rng).unwrap(), msg);
+    }
+}
+
+#[test]
+fn rsapkcs15_matches_openssl() {
+    let (key, vectors) = load("interop/rsapkcs15.txt");
+    let (sk, pk) = rsa_key(&key);
+    let mut rng = OsRandGen::new();
+
+    assert!(!vectors.is_empty());
+
+    // Invalid ciphertexts decrypt to the synthetic message, so both kinds of
+    // vectors are checked the same way
+    for v in vectors {
+        let ct = v.bytes("ct");
+
+        assert_eq!(
+            RSAPKCS15::decrypt(&pk, &sk, &ct, &mut rng).unwrap(),
+            v.bytes("msg")
+        );
+    }
+}
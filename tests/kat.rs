@@ -0,0 +1,139 @@
+// Known-answer tests against published vectors, see the header of each file
+// in tests/vectors. The Wycheproof and draft-irtf-cfrg-rsa-guidance vectors
+// are not part of the tree yet: their tests are ignored until the files are
+// added, then run with cargo test --test kat -- --ignored.
+
+mod common;
+
+use pke_rust::{
+    rsa::{algorithms::RSA, keys::RSASecretKey},
+    rsaoaep::algorithms::RSAOAEP,
+    rsapkcs15::algorithms::RSAPKCS15,
+    traits::public_enc::PublicEnc,
+    utils::rand::OsRandGen,
+};
+use rand_core::{CryptoRng, RngCore};
+use serde_json::Value;
+use sha1::Sha1;
+
+use common::{hex, load, read, rsa_key};
+
+// Hands out the bytes of a vector's seed, for the schemes drawing all their
+// randomness in one go. Panics if more is asked for.
+struct ReplayRng(Vec<u8>);
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        assert!(dest.len() <= self.0.len(), "seed exhausted");
+
+        let rest = self.0.split_off(dest.len());
+        dest.copy_from_slice(&self.0);
+        self.0 = rest;
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ReplayRng {}
+
+#[test]
+fn rsaoaep_known_answers() {
+    let (key, vectors) = load("vectors/rsaoaep.txt");
+    let (sk, pk) = rsa_key(&key);
+    let mut rng = OsRandGen::new();
+
+    assert!(!vectors.is_empty());
+
+    for v in vectors {
+        let msg = v.bytes("msg");
+        let ct = v.bytes("ct");
+
+        let mut seed = ReplayRng(v.bytes("seed"));
+        assert_eq!(
+            RSAOAEP::<Sha1>::encrypt_with_label(&pk, &msg, &[], &mut seed).unwrap(),
+            ct
+        );
+        assert!(seed.0.is_empty());
+
+        assert_eq!(
+            RSAOAEP::<Sha1>::decrypt_with_label(&pk, &sk, &ct, &[], &mut rng).unwrap(),
+            msg
+        );
+    }
+}
+
+// rsa_pkcs1_2048_test.json of Wycheproof, unmodified. Every group gives its
+// key as a PKCS#8 DER.
+#[test]
+#[ignore = "needs tests/vectors/wycheproof/rsa_pkcs1_2048_test.json"]
+fn wycheproof_rsapkcs15_known_answers() {
+    let json: Value = serde_json::from_str(&read("vectors/wycheproof/rsa_pkcs1_2048_test.json"))
+        .expect("malformed JSON");
+    let mut rng = OsRandGen::new();
+    let mut count = 0;
+
+    for group in json["testGroups"].as_array().expect("missing testGroups") {
+        let der = hex(group["privateKeyPkcs8"].as_str().expect("missing key"));
+        let (sk, pk) = RSASecretKey::from_pkcs8_der(&der).unwrap();
+
+        for test in group["tests"].as_array().expect("missing tests") {
+            let id = &test["tcId"];
+            let msg = hex(test["msg"].as_str().expect("missing msg"));
+            let ct = hex(test["ct"].as_str().expect("missing ct"));
+
+            if test["result"] == "valid" {
+                // RSA gives EM = 0x00 || 0x02 || PS || 0x00 || M, without its
+                // leading zero
+                let em = RSA::decrypt(&pk, &sk, &ct, &mut rng).unwrap();
+                assert_eq!(em[0], 2, "tcId {id}");
+                assert!(em.ends_with(&[&[0], msg.as_slice()].concat()), "tcId {id}");
+
+                assert_eq!(
+                    RSAPKCS15::decrypt(&pk, &sk, &ct, &mut rng).unwrap(),
+                    msg,
+                    "tcId {id}"
+                );
+            } else if let Ok(m) = RSAPKCS15::decrypt(&pk, &sk, &ct, &mut rng) {
+                // Invalid padding gives the synthetic message, never M
+                assert!(msg.is_empty() || m != msg, "tcId {id}");
+            }
+
+            count += 1;
+        }
+    }
+
+    assert!(count > 0);
+}
+
+// Implicit rejection vectors of draft-irtf-cfrg-rsa-guidance, in the format
+// of tests/interop/rsapkcs15.txt: the key components, then ct and the
+// message, real or synthetic, expected from decryption
+#[test]
+#[ignore = "needs tests/vectors/rsa_implicit_rejection.txt"]
+fn rsapkcs15_implicit_rejection_known_answers() {
+    let (key, vectors) = load("vectors/rsa_implicit_rejection.txt");
+    let (sk, pk) = rsa_key(&key);
+    let mut rng = OsRandGen::new();
+
+    assert!(!vectors.is_empty());
+
+    for v in vectors {
+        let ct = v.bytes("ct");
+
+        assert_eq!(
+            RSAPKCS15::decrypt(&pk, &sk, &ct, &mut rng).unwrap(),
+            v.bytes("msg")
+        );
+    }
+}
@@ -0,0 +1,92 @@
+// Regression tests: the goldens in tests/regression pin the output of this
+// crate, seeded ciphertexts included, so that any change to it is noticed.
+// They were checked with Python but are not known-answer vectors.
+
+mod common;
+
+use pke_rust::{
+    el_gamal::{
+        algorithms::ElGamal,
+        groups::NamedGroup,
+        keys::{ElGamalPublicKey, ElGamalSecretKey},
+    },
+    paillier::{
+        algorithms::Paillier,
+        keys::{PaillierPublicKey, PaillierSecretKey},
+    },
+    traits::public_enc::PublicEnc,
+    utils::rand::{DeterministicRandGen, OsRandGen},
+};
+use rug::Complete;
+
+use common::{load, Block};
+
+// Deterministic generator, for the goldens that pin the randomness
+fn seeded_rng(block: &Block) -> DeterministicRandGen {
+    let seed = block.bytes("seed").try_into().expect("seeds are 32 bytes");
+
+    DeterministicRandGen::from_seed(seed)
+}
+
+#[test]
+fn paillier_regression() {
+    let (key, vectors) = load("regression/paillier.txt");
+
+    let n = key.int("n");
+    let n_square = n.square_ref().complete();
+    let pk = PaillierPublicKey::new(n, n_square, key.int("g"));
+    let sk = PaillierSecretKey::new(
+        key.int("p"),
+        key.int("q"),
+        key.int("h_p"),
+        key.int("h_q"),
+        key.int("q_inv"),
+        key.int("lambda"),
+        key.int("mu"),
+    );
+
+    pk.validate().unwrap();
+    sk.validate_pair(&pk).unwrap();
+
+    assert!(!vectors.is_empty());
+
+    for v in vectors {
+        let msg = v.bytes("msg");
+        let ct = v.bytes("ct");
+
+        if v.get("seed").is_some() {
+            let mut rng = seeded_rng(&v);
+            assert_eq!(Paillier::encrypt(&pk, &msg, &mut rng).unwrap(), ct);
+        }
+
+        let mut rng = OsRandGen::new();
+        assert_eq!(Paillier::decrypt(&pk, &sk, &ct, &mut rng).unwrap(), msg);
+    }
+}
+
+#[test]
+fn el_gamal_regression() {
+    let (key, vectors) = load("regression/el_gamal.txt");
+
+    let group: NamedGroup = key.get("group").unwrap().parse().unwrap();
+    let pk = ElGamalPublicKey::new(group.p(), group.q(), group.g(), key.int("h"));
+    let sk = ElGamalSecretKey::new(key.int("x"));
+
+    pk.validate().unwrap();
+    sk.validate_pair(&pk).unwrap();
+
+    assert!(!vectors.is_empty());
+
+    for v in vectors {
+        let msg = v.bytes("msg");
+        let ct = v.bytes("ct");
+
+        if v.get("seed").is_some() {
+            let mut rng = seeded_rng(&v);
+            assert_eq!(ElGamal::encrypt(&pk, &msg, &mut rng).unwrap(), ct);
+        }
+
+        let mut rng = OsRandGen::new();
+        assert_eq!(ElGamal::decrypt(&pk, &sk, &ct, &mut rng).unwrap(), msg);
+    }
+}